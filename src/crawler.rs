@@ -0,0 +1,654 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::time::Duration;
+use percent_encoding::percent_decode_str;
+use reqwest::{StatusCode, Url};
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+
+use crate::fetch::{ErrorCategory, FetchError, Fetched, Fetcher, Redirect};
+use crate::frontier::{Frontier, Target};
+use crate::links::{parse_page, LinkKind, Page};
+use crate::method::{CheckMethod, CheckMethods};
+use crate::output::{self, Event};
+use crate::politeness::{Limits, Politeness};
+use crate::report::{self, ErrorEntry};
+use crate::robots::Robots;
+use crate::rules::{Action, Rules};
+use crate::scope::Scope;
+use crate::status::AcceptPolicy;
+
+/// A visited URL, along with the time it took and the result of the visit.
+type Visit = (Target, Duration, Result<(Fetched, Page), FetchError>);
+
+/// Fetch a page and extract the links and anchors it contains. This is the
+/// unit of work executed by the workers of the pool.
+///
+/// URLs which aren't crawled are only checked with the given method: their
+/// body is never downloaded, like the body of the crawled URLs which turn out
+/// not to be HTML documents.
+async fn visit(fetcher: Fetcher, target: Target, method: CheckMethod) -> Visit {
+    let start = Instant::now();
+    let result = match method {
+        _ if target.crawl => fetcher.check_url(&target.url).await,
+        CheckMethod::Head => fetcher.check_head(&target.url).await,
+        CheckMethod::Get => fetcher.check_status(&target.url).await,
+    };
+
+    let result = result.map(|fetched| {
+        let page = match &fetched.body {
+            Some(html) => parse_page(&fetched.url, html),
+            None => Page::default(),
+        };
+        (fetched, page)
+    });
+    (target, start.elapsed(), result)
+}
+
+/// Why a visited URL is reported as broken.
+#[derive(Debug, Clone)]
+pub enum Failure {
+    /// The server answered with an unexpected status code.
+    Status(StatusCode),
+    /// The request could not be completed.
+    Error(FetchError),
+}
+
+impl Failure {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Failure::Status(_) => ErrorCategory::Http,
+            Failure::Error(error) => error.category,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Status(status) => write!(f, "{}", status),
+            Failure::Error(error) => write!(f, "{}", error.message),
+        }
+    }
+}
+
+/// A page referencing an URL.
+#[derive(Debug, Clone)]
+pub struct Referrer {
+    /// The URL of the referencing page.
+    pub page: String,
+    /// The text content of the element holding the link.
+    pub text: String,
+    /// The HTML element holding the link.
+    pub element: &'static str,
+    /// The attribute of the element holding the link.
+    pub attribute: &'static str,
+}
+
+impl fmt::Display for Referrer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}[{}]", self.page, self.element, self.attribute)?;
+        if !self.text.is_empty() {
+            write!(f, " \"{}\"", self.text)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// What has been learnt about a fetched URL.
+#[derive(Debug, Clone)]
+pub struct Check {
+    /// The number of link hops from the start URL at which it was discovered.
+    pub depth: usize,
+    /// What the URL points to, according to the first link found to it.
+    pub kind: LinkKind,
+    /// The status of the response, unless the request failed.
+    pub status: Option<StatusCode>,
+    /// The media type of the response, if any.
+    pub content_type: Option<String>,
+    /// The time spent fetching the URL, retries and redirects included.
+    pub duration: Duration,
+}
+
+/// The result of a complete crawl.
+pub struct CrawlReport {
+    /// Every visited URL, along with the depth at which it was discovered.
+    pub visited: HashMap<String, usize>,
+    /// Every checked external URL.
+    pub external: HashSet<String>,
+    /// Every fetched URL, internal or external, along with its details.
+    pub checks: HashMap<String, Check>,
+    /// Every broken URL, along with the reason of the failure.
+    pub broken: HashMap<String, Failure>,
+    /// Every discovered URL, along with the pages referencing it.
+    pub referrers: HashMap<String, Vec<Referrer>>,
+    /// Every URL which only succeeded after being retried, along with the
+    /// number of attempts it took.
+    pub retried: HashMap<String, u32>,
+    /// Every URL which has been redirected, along with its redirect chain.
+    pub redirects: HashMap<String, Vec<Redirect>>,
+    /// Every link whose fragment doesn't match any anchor of the page it
+    /// points to, along with the pages referencing it.
+    pub missing_anchors: HashMap<String, Vec<Referrer>>,
+    /// Every URL which hasn't been fetched because of the robots.txt rules.
+    pub disallowed: HashSet<String>,
+    /// Every discovered URL left unvisited because of the depth or page limits.
+    pub unvisited: HashSet<String>,
+    /// The number of links filtered by each rule.
+    pub filtered: HashMap<String, usize>,
+    /// Whether the crawl was interrupted before visiting every URL.
+    pub incomplete: bool,
+}
+
+/// The settings of a crawl.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The maximum number of URLs fetched at the same time.
+    pub concurrency: usize,
+    /// Whether links to other websites should be checked.
+    pub check_external: bool,
+    /// The URLs considered as part of the crawled website.
+    pub scope: Scope,
+    /// The rules filtering the links to check and the pages to crawl.
+    pub rules: Rules,
+    /// The status codes denoting a working link.
+    pub accept: AcceptPolicy,
+    /// How the external links are checked.
+    pub check_methods: CheckMethods,
+    /// The User-Agent sent with every request, used to select the rules of
+    /// the robots.txt files.
+    pub user_agent: String,
+    /// Whether the robots.txt files should be ignored.
+    pub ignore_robots: bool,
+    /// The rate limits applied to every host.
+    pub politeness: Politeness,
+    /// The maximum number of link hops from the start URL. When set, pages
+    /// are crawled level by level so that the depth of each URL is the one of
+    /// its shortest path, whatever the order in which the pages complete.
+    pub max_depth: Option<usize>,
+    /// The maximum number of pages of the website to visit.
+    pub max_pages: Option<usize>,
+    /// The time budget of the whole crawl. Once elapsed, no new URL is
+    /// scheduled and the crawl ends with what has been collected so far.
+    pub max_duration: Option<Duration>,
+}
+
+/// A message sent by a worker to the scheduler.
+enum Message {
+    /// A page has been fetched, in the given time.
+    Page(Box<Visit>),
+    /// The robots.txt of a host has been fetched.
+    Robots(String, Robots),
+}
+
+/// What the scheduler knows about a host.
+#[derive(Debug)]
+struct Host {
+    /// The robots.txt rules of the host, once fetched.
+    robots: Option<Robots>,
+    /// The rate limits applied to the host.
+    limits: Limits,
+    /// The number of requests currently sent to the host.
+    in_flight: usize,
+    /// The earliest time at which the next request can be sent to the host.
+    next_request: Option<Instant>,
+}
+
+impl Host {
+    fn new(robots: Option<Robots>, limits: Limits) -> Self {
+        Host { robots, limits, in_flight: 0, next_request: None }
+    }
+
+    /// Check whether a request can be sent to the host right now.
+    fn is_ready(&self, now: Instant) -> bool {
+        self.robots.is_some()
+            && self.next_request.is_none_or(|time| time <= now)
+            && self.limits.max_in_flight.is_none_or(|max| self.in_flight < max)
+    }
+
+    /// Keep track of a request sent to the host. The next one will have to
+    /// wait for the longest of the configured interval and the crawl delay.
+    fn start_request(&mut self, now: Instant) {
+        let crawl_delay = self.robots.as_ref().and_then(|robots| robots.crawl_delay);
+        self.next_request = self.limits.interval().max(crawl_delay).map(|delay| now + delay);
+        self.in_flight += 1;
+    }
+}
+
+/// Check whether a fragment designates a location of a page with the given
+/// anchors. The empty fragment and `top` designate the top of any page, and
+/// text fragments (`#:~:text=...`) are highlights rather than anchors.
+fn has_anchor(anchors: &HashSet<String>, fragment: &str) -> bool {
+    let fragment = percent_decode_str(fragment).decode_utf8_lossy();
+    fragment.is_empty()
+        || fragment.eq_ignore_ascii_case("top")
+        || fragment.starts_with(":~:")
+        || anchors.contains(fragment.as_ref())
+}
+
+/// Report the outcome of a fetched URL to the event stream.
+fn emit_checked(target: &Target, check: &Check, failure: Option<&Failure>) {
+    output::emit(&Event::Checked {
+        url: &target.url,
+        kind: target.kind.label(),
+        depth: target.depth,
+        external: target.external,
+        ok: failure.is_none(),
+        status: check.status.map(|status| status.as_u16()),
+        content_type: check.content_type.as_deref(),
+        duration_ms: report::millis(check.duration),
+        error: failure.map(|failure| ErrorEntry {
+            category: failure.category().id(),
+            message: failure.to_string(),
+        }),
+    });
+}
+
+/// Extract the origin (scheme, host and port) of an URL, which identifies
+/// the server hosting it.
+fn origin_of(url: &str) -> String {
+    Url::parse(url)
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_default()
+}
+
+/// A crawler dispatching page fetches to a bounded pool of workers.
+///
+/// The scheduler owns the frontier: it hands URLs out to at most
+/// `concurrency` workers at a time and merges the links they discover
+/// back into the frontier until there is nothing left to visit. URLs of a
+/// host are only handed out once its robots.txt is known and while its
+/// rate limits allow it.
+pub struct Crawler {
+    config: Config,
+    /// Performs the requests on behalf of every worker.
+    fetcher: Fetcher,
+}
+
+impl Crawler {
+    pub fn new(config: Config, fetcher: Fetcher) -> Self {
+        Crawler {
+            config: Config { concurrency: config.concurrency.max(1), ..config },
+            fetcher,
+        }
+    }
+
+    /// Make sure the robots.txt of the host serving an URL is known, or
+    /// queued to be fetched, before any request is sent to it.
+    fn request_robots(&self, hosts: &mut HashMap<String, Host>, url: &str, robots_queue: &mut VecDeque<String>) {
+        let origin = origin_of(url);
+        if hosts.contains_key(&origin) {
+            return
+        }
+
+        let host = Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string)).unwrap_or_default();
+        let limits = self.config.politeness.limits_for(&host);
+
+        if self.config.ignore_robots {
+            hosts.insert(origin, Host::new(Some(Robots::allow_all()), limits));
+            return
+        }
+        hosts.insert(origin.clone(), Host::new(None, limits));
+        robots_queue.push_back(origin);
+    }
+
+    /// Fetch the robots.txt of a host in the background.
+    fn fetch_robots(&self, origin: String, tx: &mpsc::UnboundedSender<Message>) {
+        let tx = tx.clone();
+        let fetcher = self.fetcher.clone();
+        let user_agent = self.config.user_agent.clone();
+        tokio::spawn(async move {
+            // A missing or unreachable robots.txt doesn't restrict anything:
+            // the URLs of the host are checked and report their own failures
+            let url = format!("{}/robots.txt", origin);
+            let task = tokio::spawn(async move {
+                match fetcher.download(&url).await {
+                    Ok(fetched) if fetched.status.is_success() => Robots::parse(&fetched.body.unwrap_or_default(), &user_agent),
+                    _ => Robots::allow_all(),
+                }
+            });
+            // The host must not wait forever for a task which died
+            let robots = task.await.unwrap_or_else(|_| Robots::allow_all());
+            let _ = tx.send(Message::Robots(origin, robots));
+        });
+    }
+
+    /// Crawl the website starting from the given URL.
+    pub async fn run(&self, url: String) -> CrawlReport {
+        let mut visited = HashMap::<String, usize>::new();
+        let mut external = HashSet::<String>::new();
+        let mut checks = HashMap::<String, Check>::new();
+        let mut broken_link = HashMap::<String, Failure>::new();
+        let mut referrers = HashMap::<String, Vec<Referrer>>::new();
+        let mut retried = HashMap::<String, u32>::new();
+        let mut redirects = HashMap::<String, Vec<Redirect>>::new();
+        let mut disallowed = HashSet::<String>::new();
+        let mut unvisited = HashSet::<String>::new();
+        let mut filtered = HashMap::<String, HashSet<String>>::new();
+        let mut anchors = HashMap::<String, HashSet<String>>::new();
+        let mut fragments = Vec::<(String, String, Referrer)>::new();
+        let mut pages = 0;
+        let mut hosts = HashMap::<String, Host>::new();
+        let mut frontier = Frontier::new();
+        // The hosts whose robots.txt has to be fetched
+        let mut robots_queue = VecDeque::<String>::new();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut in_flight = 0;
+        // The number of pages being crawled at each depth
+        let mut crawling = BTreeMap::<usize, usize>::new();
+        let mut incomplete = false;
+
+        // A deadline too far away to be represented is no deadline at all
+        let deadline = self.config.max_duration.and_then(|duration| Instant::now().checked_add(duration));
+
+        self.request_robots(&mut hosts, &url, &mut robots_queue);
+        frontier.push(Target { origin: origin_of(&url), url, depth: 0, kind: LinkKind::Page, external: false, crawl: true });
+
+        loop {
+            // Fill the pool with pending URLs, up to the concurrency limit.
+            // URLs of hosts which can't be requested yet are left in the
+            // frontier, while the ones of other hosts go ahead.
+            while in_flight < self.config.concurrency {
+                // Once enough pages have been visited, the remaining ones are
+                // left aside while external links are still checked
+                if self.config.max_pages.is_some_and(|max| pages >= max) {
+                    unvisited.extend(frontier.drain_pages().into_iter().map(|target| target.url));
+                }
+
+                // A robots.txt is a request like any other, which counts
+                // against the limits of the crawl and of its host
+                if let Some(origin) = robots_queue.pop_front() {
+                    if let Some(host) = hosts.get_mut(&origin) {
+                        host.start_request(Instant::now());
+                    }
+                    self.fetch_robots(origin, &tx);
+                    in_flight += 1;
+                    continue;
+                }
+
+                // With a depth limit, pages are crawled level by level, so that
+                // each one is reached through its shortest path before its own
+                // links are followed
+                let max_page_depth = match self.config.max_depth {
+                    Some(_) => crawling.keys().next().copied().into_iter().chain(frontier.min_page_depth()).min().unwrap_or(usize::MAX),
+                    None => usize::MAX,
+                };
+
+                let now = Instant::now();
+                let target = frontier.pop_ready(|origin| hosts.get(origin).is_some_and(|host| host.is_ready(now)), max_page_depth);
+                let target = match target {
+                    Some(target) => target,
+                    None => break,
+                };
+
+                let host = hosts.get_mut(&target.origin).unwrap();
+                let robots = host.robots.as_ref().unwrap();
+                if !Url::parse(&target.url).map_or(true, |url| robots.is_allowed(&url)) {
+                    progress!("🤖 {} [disallowed by robots.txt]", &target.url);
+                    output::emit(&Event::Skipped { url: &target.url, page: None, rule: "robots.txt" });
+                    disallowed.insert(target.url);
+                    continue;
+                }
+                host.start_request(now);
+
+                if target.external {
+                    external.insert(target.url.clone());
+                } else {
+                    visited.insert(target.url.clone(), target.depth);
+                }
+                if target.crawl {
+                    pages += 1;
+                    *crawling.entry(target.depth).or_default() += 1;
+                }
+
+                // Only the status of external links matters, internal ones
+                // are requested like a browser would
+                let method = if target.external {
+                    let host = Url::parse(&target.url).ok().and_then(|url| url.host_str().map(str::to_string)).unwrap_or_default();
+                    self.config.check_methods.method_for(&host)
+                } else {
+                    CheckMethod::Get
+                };
+
+                let tx = tx.clone();
+                let fetcher = self.fetcher.clone();
+                tokio::spawn(async move {
+                    // A worker which died is reported as a failed visit, so
+                    // that the scheduler doesn't wait for it forever
+                    let task = tokio::spawn(visit(fetcher, target.clone(), method));
+                    let visit = task.await.unwrap_or_else(|error| {
+                        let error = FetchError { category: ErrorCategory::Other, message: error.to_string() };
+                        (target, Duration::default(), Err(error))
+                    });
+                    // The receiver only goes away once the crawl is over
+                    let _ = tx.send(Message::Page(Box::new(visit)));
+                });
+                in_flight += 1;
+            }
+
+            let fetching_robots = hosts.values().any(|host| host.robots.is_none());
+            if in_flight == 0 && !fetching_robots && frontier.is_empty() {
+                break;
+            }
+
+            // Wake up when the next throttled host can be requested again
+            let now = Instant::now();
+            let wake_up = hosts.values().filter_map(|host| host.next_request).filter(|time| *time > now).min();
+
+            let message = tokio::select! {
+                message = rx.recv() => message,
+                _ = sleep_until(wake_up.unwrap_or(now)), if wake_up.is_some() => continue,
+                _ = sleep_until(deadline.unwrap_or(now)), if deadline.is_some() => {
+                    // Requests still in flight are abandoned
+                    incomplete = true;
+                    break;
+                }
+            };
+
+            let (target, duration, result) = match message {
+                Some(Message::Page(page)) => *page,
+                Some(Message::Robots(origin, robots)) => {
+                    in_flight -= 1;
+                    if let Some(host) = hosts.get_mut(&origin) {
+                        host.in_flight -= 1;
+                        host.robots = Some(robots);
+                    }
+                    continue;
+                }
+                None => break,
+            };
+            in_flight -= 1;
+            if let Some(host) = hosts.get_mut(&target.origin) {
+                host.in_flight -= 1;
+            }
+            if target.crawl {
+                if let Some(count) = crawling.get_mut(&target.depth) {
+                    *count -= 1;
+                    if *count == 0 {
+                        crawling.remove(&target.depth);
+                    }
+                }
+            }
+
+            checks.insert(target.url.clone(), Check {
+                depth: target.depth,
+                kind: target.kind,
+                status: result.as_ref().ok().map(|(fetched, _)| fetched.status),
+                content_type: result.as_ref().ok().and_then(|(fetched, _)| fetched.content_type.clone()),
+                duration,
+            });
+
+            // A network failure only affects the current page, the crawl goes on
+            let (fetched, Page { mut links, anchors: page_anchors }) = match result {
+                Ok(page) => page,
+                Err(error) => {
+                    progress!("❌ {} ({})", &target.url, &error);
+                    let failure = Failure::Error(error);
+                    emit_checked(&target, &checks[&target.url], Some(&failure));
+                    broken_link.insert(target.url, failure);
+                    continue;
+                }
+            };
+
+            // A page redirected outside of the website must not be crawled
+            if !self.config.scope.contains(fetched.url.as_str()) {
+                links.clear();
+            }
+
+            let depth = target.depth + 1;
+            let mut discovered = 0;
+            for link in links {
+                let is_external = !self.config.scope.contains(&link.url);
+                if is_external && !self.config.check_external {
+                    continue;
+                }
+
+                let (action, rule) = self.config.rules.action(&link.url, is_external);
+                if action == Action::Skip {
+                    let rule = rule.unwrap_or_default();
+                    output::emit(&Event::Skipped { url: &link.url, page: Some(&target.url), rule: &rule });
+                    filtered.entry(rule).or_default().insert(link.url.clone());
+                    continue;
+                }
+                // Assets are never crawled, so the rules keeping a link from
+                // being crawled only matter for pages
+                if let Some(rule) = rule.filter(|_| link.kind.is_page()) {
+                    filtered.entry(rule).or_default().insert(link.url.clone());
+                }
+
+                let referrer = Referrer {
+                    page: target.url.clone(),
+                    text: link.text,
+                    element: link.element,
+                    attribute: link.attribute,
+                };
+                // Fragments are checked once every page has been visited
+                if let Some(fragment) = link.fragment {
+                    fragments.push((link.url.clone(), fragment, referrer.clone()));
+                }
+                referrers.entry(link.url.clone()).or_default().push(referrer);
+
+                // The link may still be reached later through a shorter path
+                if self.config.max_depth.is_some_and(|max| depth > max) {
+                    if !frontier.has_seen(&link.url) {
+                        unvisited.insert(link.url);
+                    }
+                    continue;
+                }
+                unvisited.remove(&link.url);
+
+                // Assets are checked, but only pages are crawled
+                let next = Target {
+                    url: link.url.clone(),
+                    origin: origin_of(&link.url),
+                    depth,
+                    kind: link.kind,
+                    external: is_external,
+                    crawl: action == Action::Crawl && link.kind.is_page(),
+                };
+                if frontier.push(next) {
+                    output::emit(&Event::Discovered {
+                        url: &link.url,
+                        page: &target.url,
+                        kind: link.kind.label(),
+                        depth,
+                        external: is_external,
+                    });
+                    self.request_robots(&mut hosts, &link.url, &mut robots_queue);
+                    discovered += 1;
+                }
+            }
+
+            if !fetched.redirects.is_empty() {
+                redirects.insert(target.url.clone(), fetched.redirects);
+            }
+
+            let kind = if target.kind.is_page() { String::new() } else { format!(" ({})", target.kind.label()) };
+            if !self.config.accept.accepts(&target.url, fetched.status) {
+                progress!("❌ {} [{}]{}", &target.url, &fetched.status, kind);
+                let failure = Failure::Status(fetched.status);
+                emit_checked(&target, &checks[&target.url], Some(&failure));
+                broken_link.insert(target.url, failure);
+            } else {
+                progress!("✅ {} [{}]{}", &target.url, &fetched.status, kind);
+                emit_checked(&target, &checks[&target.url], None);
+                // Same-page links are resolved against the final URL of the
+                // page. The anchors of a truncated page may be incomplete, so
+                // its fragments are left unchecked.
+                if fetched.body.is_some() && !fetched.truncated {
+                    anchors.insert(fetched.url.to_string(), page_anchors.clone());
+                    anchors.insert(target.url.clone(), page_anchors);
+                }
+                if fetched.attempts > 1 {
+                    retried.insert(target.url.clone(), fetched.attempts);
+                }
+                if discovered > 0 {
+                    progress!("➡️ {} link(s) reconciled.", discovered);
+                }
+            }
+        }
+
+        // Only the fragments of the parsed pages can be checked
+        let mut missing_anchors = HashMap::<String, Vec<Referrer>>::new();
+        for (url, fragment, referrer) in fragments {
+            if anchors.get(&url).is_some_and(|anchors| !has_anchor(anchors, &fragment)) {
+                missing_anchors.entry(format!("{}#{}", url, fragment)).or_default().push(referrer);
+            }
+        }
+
+        let filtered = filtered.into_iter().map(|(rule, links)| (rule, links.len())).collect();
+
+        CrawlReport {
+            visited,
+            external,
+            checks,
+            broken: broken_link,
+            referrers,
+            retried,
+            redirects,
+            missing_anchors,
+            disallowed,
+            unvisited,
+            filtered,
+            incomplete,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchors(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn has_anchor_matches_ids_exactly() {
+        let anchors = anchors(&["setup", "Install"]);
+        assert!(has_anchor(&anchors, "setup"));
+        assert!(has_anchor(&anchors, "Install"));
+        assert!(!has_anchor(&anchors, "Setup"));
+        assert!(!has_anchor(&anchors, "install"));
+        assert!(!has_anchor(&anchors, "missing"));
+    }
+
+    #[test]
+    fn has_anchor_decodes_fragments() {
+        let anchors = anchors(&["café", "a b"]);
+        assert!(has_anchor(&anchors, "caf%C3%A9"));
+        assert!(has_anchor(&anchors, "a%20b"));
+        assert!(!has_anchor(&anchors, "a%2520b"));
+    }
+
+    #[test]
+    fn has_anchor_accepts_the_top_of_the_page_and_text_fragments() {
+        let anchors = anchors(&[]);
+        assert!(has_anchor(&anchors, ""));
+        assert!(has_anchor(&anchors, "top"));
+        assert!(has_anchor(&anchors, "TOP"));
+        assert!(has_anchor(&anchors, ":~:text=hello%20world"));
+        assert!(!has_anchor(&anchors, "bottom"));
+    }
+}
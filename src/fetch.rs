@@ -0,0 +1,504 @@
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, LOCATION, RETRY_AFTER};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Method, Proxy, StatusCode, Url};
+use tokio::time::sleep;
+
+use crate::output::{self, Event};
+
+/// The reason why a link is considered as broken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorCategory {
+    /// The server answered with an unexpected status code.
+    Http,
+    /// The host name could not be resolved.
+    Dns,
+    /// The server actively refused the connection.
+    ConnectionRefused,
+    /// The connection could not be established for another reason.
+    Connect,
+    /// The TLS handshake failed (invalid certificate, unsupported protocol...).
+    Tls,
+    /// The server did not answer in time.
+    Timeout,
+    /// The response body could not be read or decoded.
+    BodyDecode,
+    /// The server redirected the request too many times.
+    TooManyRedirects,
+    /// The server redirected the request to an URL already in the chain.
+    RedirectLoop,
+    /// Any other failure.
+    Other,
+}
+
+impl ErrorCategory {
+    /// Classify a reqwest error by inspecting its kind and its source chain.
+    fn from_error(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return ErrorCategory::Timeout
+        }
+        if error.is_redirect() {
+            return ErrorCategory::TooManyRedirects
+        }
+        if error.is_body() || error.is_decode() {
+            return ErrorCategory::BodyDecode
+        }
+
+        // The underlying causes are only exposed through the source chain,
+        // so we have to look for well-known io errors and messages.
+        let mut source = error.source();
+        while let Some(cause) = source {
+            if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+                match io.kind() {
+                    std::io::ErrorKind::ConnectionRefused => return ErrorCategory::ConnectionRefused,
+                    std::io::ErrorKind::TimedOut => return ErrorCategory::Timeout,
+                    _ => {}
+                }
+            }
+            let message = cause.to_string().to_lowercase();
+            if message.contains("dns error") || message.contains("failed to lookup address") {
+                return ErrorCategory::Dns
+            }
+            if message.contains("certificate") || message.contains("ssl") || message.contains("tls") || message.contains("handshake") {
+                return ErrorCategory::Tls
+            }
+            source = cause.source();
+        }
+
+        if error.is_connect() {
+            return ErrorCategory::Connect
+        }
+        ErrorCategory::Other
+    }
+
+    /// Whether a request failing with this category may succeed if retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorCategory::ConnectionRefused | ErrorCategory::Connect | ErrorCategory::Timeout)
+    }
+
+    /// A stable identifier of the category, for the machine-readable reports.
+    pub fn id(&self) -> &'static str {
+        match self {
+            ErrorCategory::Http => "http",
+            ErrorCategory::Dns => "dns",
+            ErrorCategory::ConnectionRefused => "connection-refused",
+            ErrorCategory::Connect => "connect",
+            ErrorCategory::Tls => "tls",
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::BodyDecode => "body-decode",
+            ErrorCategory::TooManyRedirects => "too-many-redirects",
+            ErrorCategory::RedirectLoop => "redirect-loop",
+            ErrorCategory::Other => "other",
+        }
+    }
+
+    /// A human readable description of the category.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorCategory::Http => "HTTP error",
+            ErrorCategory::Dns => "DNS resolution failure",
+            ErrorCategory::ConnectionRefused => "Connection refused",
+            ErrorCategory::Connect => "Connection failure",
+            ErrorCategory::Tls => "TLS failure",
+            ErrorCategory::Timeout => "Timeout",
+            ErrorCategory::BodyDecode => "Body decoding failure",
+            ErrorCategory::TooManyRedirects => "Too many redirects",
+            ErrorCategory::RedirectLoop => "Redirect loop",
+            ErrorCategory::Other => "Other error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// A request which could not be completed.
+#[derive(Debug, Clone)]
+pub struct FetchError {
+    pub category: ErrorCategory,
+    pub message: String,
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(error: reqwest::Error) -> Self {
+        // Walk down to the root cause, which carries the useful message
+        let mut message = error.to_string();
+        let mut source = error.source();
+        while let Some(cause) = source {
+            message = cause.to_string();
+            source = cause.source();
+        }
+
+        FetchError {
+            category: ErrorCategory::from_error(&error),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.category, self.message)
+    }
+}
+
+/// The settings of the HTTP client shared by the whole crawl.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// The User-Agent header sent with every request.
+    pub user_agent: String,
+    /// The URL of a proxy through which every request is sent.
+    pub proxy: Option<String>,
+    /// Additional headers sent with every request, in the `Name: value` format.
+    pub headers: Vec<String>,
+    /// The maximum time allowed to establish a connection, and then to
+    /// receive the whole response.
+    pub timeout: Duration,
+}
+
+/// Build the HTTP client shared by every request of the crawl, so that
+/// connections are pooled and kept alive between requests.
+pub fn build_client(config: &ClientConfig) -> Result<Client, String> {
+    let mut headers = HeaderMap::new();
+    for header in &config.headers {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("'{}' is not a valid header, expected 'Name: value'", header))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| format!("'{}' is not a valid header name", name.trim()))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|_| format!("'{}' is not a valid header value", value.trim()))?;
+        headers.append(name, value);
+    }
+
+    let mut builder = Client::builder()
+        .user_agent(config.user_agent.as_str())
+        .default_headers(headers)
+        // Redirects are followed by the fetcher, to keep track of every hop
+        .redirect(Policy::none())
+        .connect_timeout(config.timeout)
+        .timeout(config.timeout);
+
+    if let Some(proxy) = &config.proxy {
+        let proxy = Proxy::all(proxy.as_str()).map_err(|_| format!("'{}' is not a valid proxy URL", proxy))?;
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().map_err(|error| error.to_string())
+}
+
+/// The status codes worth retrying, as they usually denote a transient failure.
+const RETRYABLE_STATUSES: [StatusCode; 6] = [
+    StatusCode::REQUEST_TIMEOUT,
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::INTERNAL_SERVER_ERROR,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// The longest time we accept to wait before retrying a request, whatever
+/// the backoff or the `Retry-After` header of the server say.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How failed requests are retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of retries after the first attempt.
+    pub retries: u32,
+    /// The delay before the first retry, doubled after every attempt.
+    pub delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Compute the jittered exponential backoff before the given retry. The
+    /// delay is picked at random in the upper half of the backoff window so
+    /// that concurrent workers don't retry all at once.
+    fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self.delay.saturating_mul(2u32.saturating_pow(retry - 1)).min(MAX_RETRY_DELAY);
+        let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        backoff.mul_f64(0.5 + jitter / 2.0)
+    }
+}
+
+/// Parse the `Retry-After` header of a response, which is either a number
+/// of seconds or an HTTP date.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()?
+            .duration_since(SystemTime::now())
+            .ok(),
+    }
+}
+
+/// A hop of a redirect chain.
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    /// The redirected URL.
+    pub url: String,
+    /// The redirection status code sent by the server.
+    pub status: StatusCode,
+    /// The URL the server redirected to.
+    pub location: String,
+}
+
+impl fmt::Display for Redirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.url, self.status.as_u16())
+    }
+}
+
+/// Format a redirect chain as `a (301) → b (302) → c`.
+pub fn format_chain(redirects: &[Redirect]) -> String {
+    let mut chain = redirects.iter().map(|redirect| redirect.to_string()).collect::<Vec<_>>();
+    if let Some(last) = redirects.last() {
+        chain.push(last.location.clone());
+    }
+    chain.join(" → ")
+}
+
+/// The outcome of a completed request.
+#[derive(Debug)]
+pub struct Fetched {
+    /// The final URL of the response, once redirects have been followed.
+    pub url: Url,
+    pub status: StatusCode,
+    /// The media type of the response (e.g. `text/html`), if any.
+    pub content_type: Option<String>,
+    /// The body of the response, if it has been downloaded.
+    pub body: Option<String>,
+    /// Whether the body has been cut off at the maximum body size.
+    pub truncated: bool,
+    /// The number of attempts made before getting this response.
+    pub attempts: u32,
+    /// The redirects followed before getting this response.
+    pub redirects: Vec<Redirect>,
+}
+
+/// Performs the requests of the crawl with a shared client, following
+/// redirects and retrying the ones failing because of a transient error.
+#[derive(Debug, Clone)]
+pub struct Fetcher {
+    client: Client,
+    retry: RetryPolicy,
+    /// The maximum number of redirects followed for a single request.
+    max_redirects: usize,
+    /// The maximum number of bytes downloaded from a single response.
+    max_body_size: usize,
+}
+
+/// Extract the media type of a response, without its parameters.
+fn content_type(response: &reqwest::Response) -> Option<String> {
+    let value = response.headers().get(CONTENT_TYPE)?.to_str().ok()?;
+    let media_type = value.split(';').next().unwrap_or_default().trim().to_lowercase();
+    Some(media_type).filter(|media_type| !media_type.is_empty())
+}
+
+/// Check whether a media type denotes a document whose links can be
+/// extracted. Responses without media type are assumed to be HTML, as
+/// browsers would most likely render them as such.
+fn is_html(content_type: Option<&str>) -> bool {
+    matches!(content_type, None | Some("text/html") | Some("application/xhtml+xml"))
+}
+
+impl Fetcher {
+    pub fn new(client: Client, retry: RetryPolicy, max_redirects: usize, max_body_size: usize) -> Self {
+        Fetcher { client, retry, max_redirects, max_body_size }
+    }
+
+    /// Download the body of a response, up to the maximum body size, and
+    /// decode it according to the charset it declares. Larger bodies are
+    /// truncated, which is told by the returned flag.
+    async fn read_body(&self, mut response: reqwest::Response) -> Result<(String, bool), FetchError> {
+        let charset = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .split(';')
+                    .filter_map(|param| param.split_once('='))
+                    .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+                    .map(|(_, charset)| charset.trim().trim_matches('"').to_string())
+            });
+
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response.chunk().await? {
+            let remaining = self.max_body_size - body.len();
+            if chunk.len() > remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                progress!("✂️ {} is larger than {} bytes, the rest of it is ignored", response.url(), self.max_body_size);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let encoding = charset
+            .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+            .unwrap_or(UTF_8);
+        Ok((encoding.decode(&body).0.into_owned(), truncated))
+    }
+
+    /// Send a request, retrying it on network errors and retryable status
+    /// codes. Returns the last response along with the number of attempts
+    /// made.
+    async fn send_with_retries(&self, method: &Method, url: &str) -> Result<(reqwest::Response, u32), FetchError> {
+        let mut attempt = 1;
+        loop {
+            let result = self.client.request(method.clone(), url).send().await.map_err(FetchError::from);
+
+            // Decide whether the request is worth retrying, and the reason why
+            let (reason, delay) = match &result {
+                Ok(response) if RETRYABLE_STATUSES.contains(&response.status()) => {
+                    (response.status().to_string(), retry_after(response))
+                }
+                Err(error) if error.category.is_retryable() => (error.to_string(), None),
+                _ => return result.map(|response| (response, attempt)),
+            };
+
+            if attempt > self.retry.retries {
+                return result.map(|response| (response, attempt))
+            }
+
+            let delay = delay.unwrap_or_else(|| self.retry.backoff(attempt)).min(MAX_RETRY_DELAY);
+            progress!("🔁 Retrying {} in {:?} ({}, retry {}/{})", url, delay, reason, attempt, self.retry.retries);
+            output::emit(&Event::Retry {
+                url,
+                reason,
+                retry: attempt,
+                retries: self.retry.retries,
+                delay_ms: delay.as_millis() as u64,
+            });
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Send a request and follow the redirects, keeping track of every
+    /// hop. Returns the final response along with the number of attempts
+    /// made and the redirect chain.
+    async fn send(&self, method: Method, url: &str) -> Result<(reqwest::Response, u32, Vec<Redirect>), FetchError> {
+        let mut url = url.to_string();
+        let mut attempts = 1;
+        let mut redirects = Vec::<Redirect>::new();
+
+        loop {
+            let (response, tries) = self.send_with_retries(&method, &url).await?;
+            attempts += tries - 1;
+
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| response.url().join(location).ok());
+
+            let location = match location {
+                Some(location) if response.status().is_redirection() && self.max_redirects > 0 => location,
+                _ => return Ok((response, attempts, redirects)),
+            };
+
+            redirects.push(Redirect {
+                url: url.clone(),
+                status: response.status(),
+                location: location.to_string(),
+            });
+
+            if redirects.iter().any(|redirect| redirect.url == location.as_str()) {
+                return Err(FetchError {
+                    category: ErrorCategory::RedirectLoop,
+                    message: format_chain(&redirects),
+                })
+            }
+            if redirects.len() > self.max_redirects {
+                return Err(FetchError {
+                    category: ErrorCategory::TooManyRedirects,
+                    message: format!("more than {} redirects: {}", self.max_redirects, format_chain(&redirects)),
+                })
+            }
+
+            url = location.to_string();
+        }
+    }
+
+    /// Send a request to the URL provided in params and return its status,
+    /// along with the body of the response if it is an HTML document. The
+    /// body of other documents is never downloaded.
+    pub async fn check_url(&self, url: &str) -> Result<Fetched, FetchError> {
+        let (response, attempts, redirects) = self.send(Method::GET, url).await?;
+        let content_type = content_type(&response);
+        let url = response.url().clone();
+        let status = response.status();
+        let (body, truncated) = if is_html(content_type.as_deref()) {
+            let (body, truncated) = self.read_body(response).await?;
+            (Some(body), truncated)
+        } else {
+            (None, false)
+        };
+
+        Ok(Fetched { url, status, content_type, body, truncated, attempts, redirects })
+    }
+
+    /// Send a request to the URL provided in params and return its status,
+    /// along with the body of the response whatever its media type.
+    pub async fn download(&self, url: &str) -> Result<Fetched, FetchError> {
+        let (response, attempts, redirects) = self.send(Method::GET, url).await?;
+        let url = response.url().clone();
+        let status = response.status();
+        let content_type = content_type(&response);
+        let (body, truncated) = self.read_body(response).await?;
+        Ok(Fetched {
+            url,
+            status,
+            content_type,
+            body: Some(body),
+            truncated,
+            attempts,
+            redirects,
+        })
+    }
+
+    /// Send a request to the URL provided in params and only check its status,
+    /// without downloading the body of the response.
+    pub async fn check_status(&self, url: &str) -> Result<Fetched, FetchError> {
+        self.request_status(Method::GET, url).await
+    }
+
+    /// Check the status of an URL with a HEAD request. Since some servers
+    /// don't implement HEAD, or answer it differently than GET, the URL is
+    /// checked again with a GET request when the HEAD one fails.
+    pub async fn check_head(&self, url: &str) -> Result<Fetched, FetchError> {
+        let head = self.request_status(Method::HEAD, url).await;
+        match &head {
+            Ok(fetched) if fetched.status.is_client_error() || fetched.status.is_server_error() => {}
+            Err(error) if error.category == ErrorCategory::Other => {}
+            _ => return head,
+        }
+        self.check_status(url).await
+    }
+
+    async fn request_status(&self, method: Method, url: &str) -> Result<Fetched, FetchError> {
+        let (response, attempts, redirects) = self.send(method, url).await?;
+        Ok(Fetched {
+            url: response.url().clone(),
+            status: response.status(),
+            content_type: content_type(&response),
+            body: None,
+            truncated: false,
+            attempts,
+            redirects,
+        })
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::links::LinkKind;
+
+/// An URL waiting to be visited.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub url: String,
+    /// The origin (scheme, host and port) of the URL, which identifies the
+    /// server hosting it.
+    pub origin: String,
+    /// The number of link hops from the start URL at which it was discovered.
+    pub depth: usize,
+    /// What the URL points to, according to the first link found to it.
+    pub kind: LinkKind,
+    /// Whether the URL belongs to another website.
+    pub external: bool,
+    /// Whether the page should be crawled, or only checked.
+    pub crawl: bool,
+}
+
+/// The pending URLs of an origin, along with their discovery order. Pages
+/// to crawl are kept apart, so that they can be dropped all at once.
+#[derive(Debug, Default)]
+struct Queue {
+    pages: VecDeque<(u64, Target)>,
+    checks: VecDeque<(u64, Target)>,
+}
+
+impl Queue {
+    /// The discovery order of the oldest URL of the queue, leaving aside the
+    /// pages deeper than the given depth.
+    fn front(&self, max_page_depth: usize) -> Option<u64> {
+        let page = self.pages.front().filter(|(_, target)| target.depth <= max_page_depth).map(|(order, _)| *order);
+        let check = self.checks.front().map(|(order, _)| *order);
+        page.into_iter().chain(check).min()
+    }
+
+    fn pop(&mut self, max_page_depth: usize) -> Option<Target> {
+        let page = self.pages.front().filter(|(_, target)| target.depth <= max_page_depth).map(|(order, _)| *order);
+        let check = self.checks.front().map(|(order, _)| *order);
+        let queue = match (page, check) {
+            (Some(page), Some(check)) if check < page => &mut self.checks,
+            (Some(_), _) => &mut self.pages,
+            (None, Some(_)) => &mut self.checks,
+            (None, None) => return None,
+        };
+        queue.pop_front().map(|(_, target)| target)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pages.is_empty() && self.checks.is_empty()
+    }
+}
+
+/// A FIFO queue of URLs to visit.
+///
+/// Every URL ever pushed is remembered in a `seen` set, so a link discovered
+/// by several pages is enqueued exactly once. URLs are handed out in the order
+/// they were discovered, which gives a breadth-first traversal of the website.
+///
+/// URLs are queued per origin, so that the URLs of an origin which can't be
+/// requested yet are skipped without looking at each of them.
+#[derive(Debug, Default)]
+pub struct Frontier {
+    queues: HashMap<String, Queue>,
+    seen: HashSet<String>,
+    /// The number of queued pages to crawl at each depth.
+    page_depths: BTreeMap<usize, usize>,
+    /// The discovery order of the next pushed URL.
+    next: u64,
+}
+
+impl Frontier {
+    pub fn new() -> Self {
+        Frontier::default()
+    }
+
+    /// Enqueue an URL. Returns false if the URL has already been seen.
+    pub fn push(&mut self, target: Target) -> bool {
+        if self.seen.contains(&target.url) {
+            return false
+        }
+        self.seen.insert(target.url.clone());
+
+        let queue = self.queues.entry(target.origin.clone()).or_default();
+        if target.crawl {
+            *self.page_depths.entry(target.depth).or_default() += 1;
+            queue.pages.push_back((self.next, target));
+        } else {
+            queue.checks.push_back((self.next, target));
+        }
+        self.next += 1;
+        true
+    }
+
+    /// Dequeue the oldest discovered URL among the origins satisfying the
+    /// predicate, so that URLs which can't be fetched yet don't hold back
+    /// the other ones. Pages to crawl deeper than the given depth are left
+    /// in the queue.
+    pub fn pop_ready<F: FnMut(&str) -> bool>(&mut self, mut is_ready: F, max_page_depth: usize) -> Option<Target> {
+        let (origin, _) = self
+            .queues
+            .iter()
+            .filter_map(|(origin, queue)| Some((origin, queue.front(max_page_depth)?)))
+            .filter(|(origin, _)| is_ready(origin))
+            .min_by_key(|(_, front)| *front)?;
+        let origin = origin.clone();
+
+        let queue = self.queues.get_mut(&origin)?;
+        let target = queue.pop(max_page_depth)?;
+        if queue.is_empty() {
+            self.queues.remove(&origin);
+        }
+        if target.crawl {
+            self.forget_page_depth(target.depth);
+        }
+        Some(target)
+    }
+
+    /// Dequeue every page to crawl, leaving the URLs to check only.
+    pub fn drain_pages(&mut self) -> Vec<Target> {
+        let mut pages = Vec::new();
+        for queue in self.queues.values_mut() {
+            pages.extend(queue.pages.drain(..).map(|(_, target)| target));
+        }
+        self.queues.retain(|_, queue| !queue.is_empty());
+        self.page_depths.clear();
+        pages
+    }
+
+    /// The depth of the shallowest queued page to crawl.
+    pub fn min_page_depth(&self) -> Option<usize> {
+        self.page_depths.keys().next().copied()
+    }
+
+    fn forget_page_depth(&mut self, depth: usize) {
+        if let Some(count) = self.page_depths.get_mut(&depth) {
+            *count -= 1;
+            if *count == 0 {
+                self.page_depths.remove(&depth);
+            }
+        }
+    }
+
+    /// Check whether an URL has already been pushed.
+    pub fn has_seen(&self, url: &str) -> bool {
+        self.seen.contains(url)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(url: &str, origin: &str, crawl: bool) -> Target {
+        Target {
+            url: url.to_string(),
+            origin: origin.to_string(),
+            depth: 0,
+            kind: LinkKind::Page,
+            external: false,
+            crawl,
+        }
+    }
+
+    fn urls(frontier: &mut Frontier) -> Vec<String> {
+        std::iter::from_fn(|| frontier.pop_ready(|_| true, usize::MAX)).map(|target| target.url).collect()
+    }
+
+    #[test]
+    fn pop_follows_discovery_order_across_origins() {
+        let mut frontier = Frontier::new();
+        frontier.push(target("a/1", "a", true));
+        frontier.push(target("b/1", "b", false));
+        frontier.push(target("a/2", "a", false));
+        frontier.push(target("a/3", "a", true));
+        frontier.push(target("b/2", "b", true));
+
+        assert_eq!(urls(&mut frontier), vec!["a/1", "b/1", "a/2", "a/3", "b/2"]);
+        assert!(frontier.is_empty());
+    }
+
+    #[test]
+    fn push_ignores_seen_urls() {
+        let mut frontier = Frontier::new();
+        assert!(frontier.push(target("a/1", "a", true)));
+        assert!(!frontier.push(target("a/1", "a", false)));
+        assert!(frontier.has_seen("a/1"));
+        assert_eq!(urls(&mut frontier), vec!["a/1"]);
+        assert!(!frontier.push(target("a/1", "a", true)));
+    }
+
+    #[test]
+    fn pop_skips_origins_which_are_not_ready() {
+        let mut frontier = Frontier::new();
+        frontier.push(target("a/1", "a", true));
+        frontier.push(target("b/1", "b", true));
+
+        assert_eq!(frontier.pop_ready(|origin| origin == "b", usize::MAX).map(|target| target.url), Some("b/1".to_string()));
+        assert!(frontier.pop_ready(|origin| origin == "b", usize::MAX).is_none());
+        assert_eq!(urls(&mut frontier), vec!["a/1"]);
+    }
+
+    #[test]
+    fn drain_pages_keeps_urls_to_check() {
+        let mut frontier = Frontier::new();
+        frontier.push(target("a/1", "a", true));
+        frontier.push(target("a/2", "a", false));
+        frontier.push(target("b/1", "b", true));
+
+        let mut drained = frontier.drain_pages().into_iter().map(|target| target.url).collect::<Vec<_>>();
+        drained.sort();
+        assert_eq!(drained, vec!["a/1", "b/1"]);
+        assert_eq!(urls(&mut frontier), vec!["a/2"]);
+    }
+
+    #[test]
+    fn pop_leaves_deeper_pages_aside() {
+        let mut frontier = Frontier::new();
+        frontier.push(Target { depth: 2, ..target("a/1", "a", true) });
+        frontier.push(Target { depth: 1, ..target("b/1", "b", true) });
+        frontier.push(Target { depth: 3, ..target("a/2", "a", false) });
+        assert_eq!(frontier.min_page_depth(), Some(1));
+
+        assert_eq!(frontier.pop_ready(|_| true, 1).map(|target| target.url), Some("b/1".to_string()));
+        assert_eq!(frontier.pop_ready(|_| true, 1).map(|target| target.url), Some("a/2".to_string()));
+        assert!(frontier.pop_ready(|_| true, 1).is_none());
+        assert_eq!(frontier.min_page_depth(), Some(2));
+        assert_eq!(frontier.pop_ready(|_| true, 2).map(|target| target.url), Some("a/1".to_string()));
+        assert_eq!(frontier.min_page_depth(), None);
+        assert!(frontier.is_empty());
+    }
+}
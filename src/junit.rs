@@ -0,0 +1,241 @@
+//! The JUnit XML report, written to stdout with `--format junit`.
+//!
+//! Each fetched URL is a test case, named after the URL and grouped by host,
+//! which fails when the link is broken. The internal links, the external
+//! links and the anchors each make a test suite.
+use std::fmt::Write;
+use std::time::{Duration, SystemTime};
+use reqwest::Url;
+
+use crate::crawler::{CrawlReport, Referrer};
+use crate::report;
+
+/// Escape a text for an XML attribute or element.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Most control characters are forbidden in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn host_of(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?)))
+        .unwrap_or_default()
+}
+
+/// A test case of the report.
+struct TestCase {
+    class: String,
+    name: String,
+    time: Duration,
+    /// The type, message and details of the failure, if any.
+    failure: Option<(&'static str, String, String)>,
+    /// Why the test has been skipped, if it has.
+    skipped: Option<&'static str>,
+}
+
+/// The message of a failure, which tells the first page holding the link,
+/// as many dashboards only show the message.
+fn failure_message(problem: String, referrers: Option<&Vec<Referrer>>) -> String {
+    match referrers.map(Vec::as_slice).unwrap_or_default() {
+        [] => problem,
+        [referrer] => format!("{} (found on {})", problem, referrer.page),
+        [referrer, others @ ..] => format!("{} (found on {} and {} other page(s))", problem, referrer.page, others.len()),
+    }
+}
+
+fn found_on(referrers: Option<&Vec<Referrer>>) -> String {
+    referrers
+        .into_iter()
+        .flatten()
+        .map(|referrer| format!("found on {}\n", referrer))
+        .collect()
+}
+
+fn write_suite(xml: &mut String, name: &str, timestamp: &str, cases: &[TestCase]) {
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+    let skipped = cases.iter().filter(|case| case.skipped.is_some()).count();
+    let time = cases.iter().map(|case| case.time).sum::<Duration>();
+
+    let _ = writeln!(
+        xml,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" errors="0" skipped="{}" time="{:.3}" timestamp="{}">"#,
+        escape(name), cases.len(), failures, skipped, time.as_secs_f64(), timestamp,
+    );
+    for case in cases {
+        let _ = write!(
+            xml,
+            r#"    <testcase classname="{}" name="{}" time="{:.3}""#,
+            escape(&case.class), escape(&case.name), case.time.as_secs_f64(),
+        );
+        match (&case.failure, case.skipped) {
+            (Some((kind, message, details)), _) => {
+                let _ = writeln!(xml, ">");
+                let _ = writeln!(
+                    xml,
+                    r#"      <failure type="{}" message="{}">{}</failure>"#,
+                    kind, escape(message), escape(details),
+                );
+                let _ = writeln!(xml, "    </testcase>");
+            }
+            (None, Some(reason)) => {
+                let _ = writeln!(xml, ">");
+                let _ = writeln!(xml, r#"      <skipped message="{}"/>"#, escape(reason));
+                let _ = writeln!(xml, "    </testcase>");
+            }
+            (None, None) => {
+                let _ = writeln!(xml, "/>");
+            }
+        }
+    }
+    let _ = writeln!(xml, "  </testsuite>");
+}
+
+/// Render the JUnit XML report of a crawl.
+pub fn render(report: &CrawlReport, started_at: SystemTime, duration: Duration) -> String {
+    let mut urls = report.checks.keys().chain(&report.disallowed).collect::<Vec<_>>();
+    urls.sort();
+
+    let (mut internal, mut external) = (Vec::new(), Vec::new());
+    for url in urls {
+        let case = TestCase {
+            class: host_of(url),
+            name: url.clone(),
+            time: report.checks.get(url).map(|check| check.duration).unwrap_or_default(),
+            failure: report.broken.get(url).map(|failure| {
+                let referrers = report.referrers.get(url);
+                (failure.category().id(), failure_message(failure.to_string(), referrers), found_on(referrers))
+            }),
+            skipped: report.disallowed.contains(url).then_some("disallowed by robots.txt"),
+        };
+        if report.external.contains(url) {
+            external.push(case);
+        } else {
+            internal.push(case);
+        }
+    }
+
+    let mut missing = report.missing_anchors.iter().collect::<Vec<_>>();
+    missing.sort_by(|a, b| a.0.cmp(b.0));
+    let anchors = missing
+        .into_iter()
+        .map(|(url, referrers)| TestCase {
+            class: host_of(url),
+            name: url.clone(),
+            time: Duration::default(),
+            failure: Some((
+                "missing-anchor",
+                failure_message("missing anchor".to_string(), Some(referrers)),
+                found_on(Some(referrers)),
+            )),
+            skipped: None,
+        })
+        .collect::<Vec<_>>();
+
+    let suites = [("internal links", internal), ("external links", external), ("anchors", anchors)];
+    let tests = suites.iter().map(|(_, cases)| cases.len()).sum::<usize>();
+    let failures = suites.iter().flat_map(|(_, cases)| cases).filter(|case| case.failure.is_some()).count();
+    let timestamp = report::timestamp(started_at);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        r#"<testsuites name="fuze" tests="{}" failures="{}" errors="0" time="{:.3}">"#,
+        tests, failures, duration.as_secs_f64(),
+    );
+    for (name, cases) in suites.iter() {
+        write_suite(&mut xml, name, &timestamp, cases);
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::crawler::{Check, Failure};
+    use crate::links::LinkKind;
+
+    fn referrer(page: &str) -> Referrer {
+        Referrer { page: page.to_string(), text: "Docs".to_string(), element: "a", attribute: "href" }
+    }
+
+    fn check(status: u16) -> Check {
+        Check {
+            depth: 1,
+            kind: LinkKind::Page,
+            status: Some(StatusCode::from_u16(status).unwrap()),
+            content_type: None,
+            duration: Duration::from_millis(250),
+        }
+    }
+
+    fn report() -> CrawlReport {
+        let ok = "https://example.com/ok".to_string();
+        let broken = "https://example.com/broken".to_string();
+        let disallowed = "https://example.com/private".to_string();
+        CrawlReport {
+            visited: HashMap::new(),
+            external: HashSet::new(),
+            checks: HashMap::from([(ok, check(200)), (broken.clone(), check(404))]),
+            broken: HashMap::from([(broken.clone(), Failure::Status(StatusCode::NOT_FOUND))]),
+            referrers: HashMap::from([(broken, vec![referrer("https://example.com/"), referrer("https://example.com/a")])]),
+            retried: HashMap::new(),
+            redirects: HashMap::new(),
+            missing_anchors: HashMap::new(),
+            disallowed: HashSet::from([disallowed]),
+            unvisited: HashSet::new(),
+            filtered: HashMap::new(),
+            incomplete: false,
+        }
+    }
+
+    #[test]
+    fn escape_handles_markup_and_control_characters() {
+        assert_eq!(escape(r#"<a href="x">Tom & 'Jerry'</a>"#), "&lt;a href=&quot;x&quot;&gt;Tom &amp; &apos;Jerry&apos;&lt;/a&gt;");
+        assert_eq!(escape("a\u{0}b\u{1b}c\u{7f}"), "abc");
+        assert_eq!(escape("line\n\tnext\r"), "line\n\tnext\r");
+    }
+
+    #[test]
+    fn failure_message_tells_the_first_page() {
+        let referrers = vec![referrer("https://example.com/")];
+        assert_eq!(failure_message("404 Not Found".to_string(), None), "404 Not Found");
+        assert_eq!(failure_message("404 Not Found".to_string(), Some(&referrers)), "404 Not Found (found on https://example.com/)");
+    }
+
+    #[test]
+    fn render_reports_passing_broken_and_skipped_links() {
+        let xml = render(&report(), SystemTime::UNIX_EPOCH, Duration::from_secs(1));
+        assert!(xml.contains(r#"<testsuites name="fuze" tests="3" failures="1" errors="0" time="1.000">"#));
+        assert!(xml.contains(r#"<testsuite name="internal links" tests="3" failures="1" errors="0" skipped="1" time="0.500""#));
+        assert!(xml.contains(r#"<testcase classname="example.com:443" name="https://example.com/ok" time="0.250"/>"#));
+        assert!(xml.contains(concat!(
+            r#"<failure type="http" message="404 Not Found (found on https://example.com/ and 1 other page(s))">"#,
+            "found on https://example.com/ (a[href] &quot;Docs&quot;)\n",
+            "found on https://example.com/a (a[href] &quot;Docs&quot;)\n",
+            "</failure>",
+        )));
+        assert!(xml.contains(concat!(
+            r#"<testcase classname="example.com:443" name="https://example.com/private" time="0.000">"#,
+            "\n      ",
+            r#"<skipped message="disallowed by robots.txt"/>"#,
+        )));
+        assert!(xml.contains(r#"<testsuite name="external links" tests="0" failures="0""#));
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashSet;
+use scraper::selector::Selector;
+use scraper::Html;
+use reqwest::Url;
+
+/// What a link points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LinkKind {
+    /// An HTML page (`a`, `area`, `link rel=canonical`, `meta refresh`...).
+    Page,
+    /// A page embedded in another one (`iframe`).
+    Frame,
+    /// An image (`img`, `source srcset`, `video poster`...).
+    Image,
+    /// A favicon (`link rel=icon`).
+    Icon,
+    Stylesheet,
+    Script,
+    /// An audio or video file, or its subtitles (`video`, `audio`, `source`, `track`).
+    Media,
+    /// An embedded object (`object`).
+    Object,
+    /// The target of a form.
+    Form,
+    /// Any other resource linked from the page (`link rel=preload`...).
+    Resource,
+}
+
+impl LinkKind {
+    /// Whether the link points to a document which should be crawled, the
+    /// other kinds of links are only checked.
+    pub fn is_page(&self) -> bool {
+        matches!(self, LinkKind::Page | LinkKind::Frame)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LinkKind::Page => "page",
+            LinkKind::Frame => "frame",
+            LinkKind::Image => "image",
+            LinkKind::Icon => "icon",
+            LinkKind::Stylesheet => "stylesheet",
+            LinkKind::Script => "script",
+            LinkKind::Media => "media",
+            LinkKind::Object => "object",
+            LinkKind::Form => "form",
+            LinkKind::Resource => "resource",
+        }
+    }
+}
+
+/// A link found in a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// The URL of the link, without its fragment.
+    pub url: String,
+    /// The fragment of the link, which should match an anchor of the page.
+    pub fragment: Option<String>,
+    pub kind: LinkKind,
+    /// The text content of the element holding the link.
+    pub text: String,
+    /// The HTML element holding the link.
+    pub element: &'static str,
+    /// The attribute of the element holding the link.
+    pub attribute: &'static str,
+}
+
+/// The elements holding links, along with their link attributes.
+const LINK_ATTRIBUTES: [(&str, &str, LinkKind); 17] = [
+    ("a", "href", LinkKind::Page),
+    ("area", "href", LinkKind::Page),
+    ("img", "src", LinkKind::Image),
+    ("img", "srcset", LinkKind::Image),
+    ("source", "srcset", LinkKind::Image),
+    ("source", "src", LinkKind::Media),
+    ("link", "href", LinkKind::Resource),
+    ("script", "src", LinkKind::Script),
+    ("iframe", "src", LinkKind::Frame),
+    ("video", "src", LinkKind::Media),
+    ("video", "poster", LinkKind::Image),
+    ("audio", "src", LinkKind::Media),
+    ("track", "src", LinkKind::Media),
+    ("object", "data", LinkKind::Object),
+    ("form", "action", LinkKind::Form),
+    ("embed", "src", LinkKind::Object),
+    ("meta", "content", LinkKind::Page),
+];
+
+/// Find the kind of a `<link>` element from its `rel` attribute. Hints
+/// pointing to a bare origin (`preconnect`, `dns-prefetch`) aren't links to
+/// check, so they have no kind.
+fn link_rel_kind(rel: &str) -> Option<LinkKind> {
+    let rel = rel.to_lowercase();
+    let rel = rel.split_whitespace().collect::<Vec<_>>();
+    if rel.contains(&"stylesheet") {
+        Some(LinkKind::Stylesheet)
+    } else if rel.iter().any(|rel| rel.contains("icon")) {
+        Some(LinkKind::Icon)
+    } else if rel.iter().any(|rel| matches!(*rel, "canonical" | "alternate" | "next" | "prev" | "prefetch" | "prerender")) {
+        // Prefetched pages often appear before the links to them, which
+        // must not turn them into resources which are never crawled
+        Some(LinkKind::Page)
+    } else if rel.iter().all(|rel| matches!(*rel, "preconnect" | "dns-prefetch")) && !rel.is_empty() {
+        None
+    } else {
+        Some(LinkKind::Resource)
+    }
+}
+
+/// Extract the URLs of a `srcset` attribute, made of comma separated image
+/// candidates followed by an optional descriptor (e.g. `a.png 1x, b.png 2x`).
+///
+/// Following the HTML parsing rules, an URL runs up to the next whitespace
+/// and may itself contain commas (e.g. `img/w_400,c_fill/a.png 1x`): only the
+/// commas ending it, or following its descriptor, separate the candidates.
+fn parse_srcset(srcset: &str) -> Vec<&str> {
+    let mut urls = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            return urls
+        }
+
+        let end = rest.find(|c: char| c.is_ascii_whitespace()).unwrap_or(rest.len());
+        let (url, descriptor) = rest.split_at(end);
+        let trimmed = url.trim_end_matches(',');
+        urls.push(trimmed);
+
+        // A candidate without descriptor ends with the commas of its URL
+        if trimmed.len() < url.len() {
+            rest = descriptor;
+            continue;
+        }
+
+        // Commas between parentheses belong to the descriptor
+        let mut depth = 0;
+        let comma = descriptor.char_indices().find(|(_, c)| match c {
+            '(' => { depth += 1; false }
+            ')' => { depth -= 1; false }
+            ',' => depth <= 0,
+            _ => false,
+        });
+        rest = match comma {
+            Some((index, _)) => &descriptor[index + 1..],
+            None => "",
+        };
+    }
+}
+
+/// Extract the URL of a `<meta http-equiv="refresh">` content, written as
+/// `<delay>; url=<url>`.
+fn parse_refresh(content: &str) -> Option<&str> {
+    let (_, target) = content.split_once([';', ','])?;
+    let target = target.trim_start();
+    if !target.get(..3)?.eq_ignore_ascii_case("url") {
+        return None
+    }
+    let target = target[3..].trim_start().strip_prefix('=')?.trim();
+    Some(target.trim_matches(|c| c == '\'' || c == '"'))
+}
+
+/// Normalize an URL by resolving it against the base URL of the page it
+/// was found in, following the WHATWG URL joining rules. Anchors (`#foo`)
+/// resolve to the base URL itself.
+pub fn normalize_url(base_url: &Url, path: &str) -> Option<Url> {
+    // Relative (`foo.html`, `../foo.html`), absolute-path (`/foo.html`) and
+    // protocol-relative (`//host/foo.html`) references are all handled by join
+    let href = base_url.join(path.trim()).ok()?;
+
+    // Links without host (mailto:, javascript:...) can't be checked
+    if !href.has_host() || !matches!(href.scheme(), "http" | "https") {
+        return None
+    }
+    Some(href)
+}
+
+/// The content of an HTML page relevant to the crawl.
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    /// The links of the page, in document order.
+    pub links: Vec<Link>,
+    /// The anchors of the page, which are the `id` attributes of its elements
+    /// and the `name` attributes of its `<a>` elements.
+    pub anchors: HashSet<String>,
+}
+
+/// Parse a raw HTML and returns the links and anchors it contains.
+///
+/// The page URL must be the one the document was actually served from, after
+/// any redirect, as relative links are resolved against it unless the document
+/// declares a `<base href>`.
+pub fn parse_page(url: &Url, html: &str) -> Page {
+    let document = Html::parse_document(html);
+    Page {
+        links: get_links(url, &document),
+        anchors: get_anchors(&document),
+    }
+}
+
+fn get_anchors(document: &Html) -> HashSet<String> {
+    let selector = Selector::parse("[id], a[name]").unwrap();
+    let mut anchors = HashSet::new();
+    for el in document.select(&selector) {
+        anchors.extend(el.value().id().map(str::to_string));
+        if el.value().name() == "a" {
+            anchors.extend(el.value().attr("name").map(str::to_string));
+        }
+    }
+    anchors
+}
+
+fn get_links(url: &Url, document: &Html) -> Vec<Link> {
+    let selector = Selector::parse("a, area, img, source, link, script, iframe, video, audio, track, object, form, embed, meta").unwrap();
+    let base_selector = Selector::parse("base[href]").unwrap();
+
+    // Only the first base element is taken into account
+    let base_url = document
+        .select(&base_selector)
+        .next()
+        .and_then(|el| url.join(el.value().attr("href")?.trim()).ok())
+        .unwrap_or_else(|| url.clone());
+
+    let mut links = Vec::new();
+    for el in document.select(&selector) {
+        let text = el.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+        // Images have no content, their alternative text describes them instead
+        let text = match el.value().attr("alt") {
+            Some(alt) if text.is_empty() => alt.split_whitespace().collect::<Vec<_>>().join(" "),
+            _ => text,
+        };
+
+        for (element, attribute, kind) in LINK_ATTRIBUTES.iter() {
+            if el.value().name() != *element {
+                continue;
+            }
+            let value = match el.value().attr(attribute) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let (kind, values) = match (*element, *attribute) {
+                ("link", _) => match link_rel_kind(el.value().attr("rel").unwrap_or_default()) {
+                    Some(kind) => (kind, vec![value]),
+                    None => continue,
+                },
+                // Checking the target of a POST form with a GET request is meaningless
+                ("form", _) if el.value().attr("method").is_some_and(|method| !method.eq_ignore_ascii_case("get")) => continue,
+                (_, "srcset") => (*kind, parse_srcset(value)),
+                ("meta", _) => {
+                    let is_refresh = el.value().attr("http-equiv").is_some_and(|equiv| equiv.eq_ignore_ascii_case("refresh"));
+                    match parse_refresh(value) {
+                        Some(target) if is_refresh => (*kind, vec![target]),
+                        _ => continue,
+                    }
+                }
+                _ => (*kind, vec![value]),
+            };
+
+            links.extend(values.into_iter().filter_map(|value| {
+                let mut url = normalize_url(&base_url, value)?;
+                let fragment = url.fragment().map(str::to_string);
+                url.set_fragment(None);
+                Some(Link {
+                    url: url.to_string(),
+                    fragment,
+                    kind,
+                    text: text.clone(),
+                    element,
+                    attribute,
+                })
+            }));
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_srcset_splits_candidates() {
+        assert_eq!(parse_srcset("a.png"), vec!["a.png"]);
+        assert_eq!(parse_srcset("a.png 1x, b.png 2x"), vec!["a.png", "b.png"]);
+        assert_eq!(parse_srcset(" a.png 480w ,b.png  800w "), vec!["a.png", "b.png"]);
+        assert_eq!(parse_srcset("a.png,b.png 2x"), vec!["a.png,b.png"]);
+        assert_eq!(parse_srcset("a.png, b.png"), vec!["a.png", "b.png"]);
+        assert_eq!(parse_srcset(""), Vec::<&str>::new());
+        assert_eq!(parse_srcset(" , ,"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn parse_srcset_keeps_commas_of_urls() {
+        assert_eq!(parse_srcset("img/w_400,c_fill/x.png 1x"), vec!["img/w_400,c_fill/x.png"]);
+        assert_eq!(
+            parse_srcset("img/w_400,c_fill/x.png 1x, img/w_800,c_fill/x.png 2x"),
+            vec!["img/w_400,c_fill/x.png", "img/w_800,c_fill/x.png"],
+        );
+        assert_eq!(parse_srcset("a.png, img/w_400,c_fill/x.png 2x"), vec!["a.png", "img/w_400,c_fill/x.png"]);
+    }
+
+    #[test]
+    fn parse_srcset_ignores_commas_of_descriptors() {
+        assert_eq!(parse_srcset("a.png calc(1px, 2px), b.png 2x"), vec!["a.png", "b.png"]);
+    }
+
+    fn urls(page_url: &str, html: &str) -> Vec<String> {
+        parse_page(&Url::parse(page_url).unwrap(), html).links.into_iter().map(|link| link.url).collect()
+    }
+
+    #[test]
+    fn normalize_url_resolves_relative_references() {
+        let base = Url::parse("https://example.com/docs/guide/intro.html").unwrap();
+        let normalize = |path: &str| normalize_url(&base, path).map(|url| url.to_string());
+        assert_eq!(normalize("foo.html").as_deref(), Some("https://example.com/docs/guide/foo.html"));
+        assert_eq!(normalize(" ./foo.html ").as_deref(), Some("https://example.com/docs/guide/foo.html"));
+        assert_eq!(normalize("../foo.html").as_deref(), Some("https://example.com/docs/foo.html"));
+        assert_eq!(normalize("../../../foo.html").as_deref(), Some("https://example.com/foo.html"));
+        assert_eq!(normalize("/foo.html").as_deref(), Some("https://example.com/foo.html"));
+        assert_eq!(normalize("//cdn.example.com/a.js").as_deref(), Some("https://cdn.example.com/a.js"));
+        assert_eq!(normalize("#setup").as_deref(), Some("https://example.com/docs/guide/intro.html#setup"));
+        assert_eq!(normalize("?page=2").as_deref(), Some("https://example.com/docs/guide/intro.html?page=2"));
+    }
+
+    #[test]
+    fn normalize_url_rejects_links_without_host() {
+        let base = Url::parse("https://example.com/").unwrap();
+        assert_eq!(normalize_url(&base, "mailto:me@example.com"), None);
+        assert_eq!(normalize_url(&base, "javascript:void(0)"), None);
+        assert_eq!(normalize_url(&base, "tel:+33000000000"), None);
+        assert_eq!(normalize_url(&base, "ftp://example.com/file"), None);
+        assert_eq!(normalize_url(&base, "http://[::1"), None);
+    }
+
+    #[test]
+    fn get_links_resolves_against_the_first_base() {
+        let html = r#"<head><base href="/static/"><base href="/other/"></head>
+            <body><a href="foo.html">Foo</a><a href="../bar.html">Bar</a></body>"#;
+        assert_eq!(urls("https://example.com/docs/index.html", html), vec![
+            "https://example.com/static/foo.html",
+            "https://example.com/bar.html",
+        ]);
+
+        let html = r#"<base href="https://cdn.example.com/v2/"><img src="a.png">"#;
+        assert_eq!(urls("https://example.com/", html), vec!["https://cdn.example.com/v2/a.png"]);
+    }
+
+    #[test]
+    fn get_links_resolves_against_the_page_without_base() {
+        let html = r##"<a href="foo.html">Foo</a><a href="mailto:me@example.com">Mail</a><a href="javascript:go()">Go</a><a href="#top">Top</a>"##;
+        assert_eq!(urls("https://example.com/docs/guide/intro.html", html), vec![
+            "https://example.com/docs/guide/foo.html",
+            "https://example.com/docs/guide/intro.html",
+        ]);
+    }
+
+    #[test]
+    fn get_anchors_collects_ids_and_anchor_names() {
+        let html = r#"<h2 id="Setup">Setup</h2><a name="legacy">Legacy</a><a id="both" name="other"></a>
+            <div name="not-an-anchor"></div><input name="field">"#;
+        let anchors = parse_page(&Url::parse("https://example.com/").unwrap(), html).anchors;
+        let mut anchors = anchors.into_iter().collect::<Vec<_>>();
+        anchors.sort();
+        assert_eq!(anchors, vec!["Setup", "both", "legacy", "other"]);
+    }
+
+    #[test]
+    fn link_rel_kind_classifies_links() {
+        assert_eq!(link_rel_kind("Stylesheet"), Some(LinkKind::Stylesheet));
+        assert_eq!(link_rel_kind("shortcut icon"), Some(LinkKind::Icon));
+        assert_eq!(link_rel_kind("canonical"), Some(LinkKind::Page));
+        assert_eq!(link_rel_kind("prefetch"), Some(LinkKind::Page));
+        assert_eq!(link_rel_kind("prerender"), Some(LinkKind::Page));
+        assert_eq!(link_rel_kind("preload"), Some(LinkKind::Resource));
+        assert_eq!(link_rel_kind(""), Some(LinkKind::Resource));
+        assert_eq!(link_rel_kind("preconnect"), None);
+        assert_eq!(link_rel_kind("dns-prefetch preconnect"), None);
+    }
+
+    #[test]
+    fn get_links_skips_connection_hints() {
+        let html = r#"<head>
+            <link rel="preconnect" href="https://fonts.gstatic.com">
+            <link rel="dns-prefetch" href="//cdn.example.com">
+            <link rel="prefetch" href="/next.html">
+        </head><body><a href="/next.html">Next</a></body>"#;
+        let page = parse_page(&Url::parse("https://example.com/").unwrap(), html);
+        let links = page.links.iter().map(|link| (link.url.as_str(), link.kind)).collect::<Vec<_>>();
+        assert_eq!(links, vec![("https://example.com/next.html", LinkKind::Page), ("https://example.com/next.html", LinkKind::Page)]);
+    }
+
+    #[test]
+    fn parse_refresh_extracts_url() {
+        assert_eq!(parse_refresh("0; url=/next.html"), Some("/next.html"));
+        assert_eq!(parse_refresh("5;URL='/next.html'"), Some("/next.html"));
+        assert_eq!(parse_refresh("0, url = \"next.html\""), Some("next.html"));
+        assert_eq!(parse_refresh("3; Url=http://example.com/"), Some("http://example.com/"));
+    }
+
+    #[test]
+    fn parse_refresh_rejects_content_without_url() {
+        assert_eq!(parse_refresh("30"), None);
+        assert_eq!(parse_refresh("0; /next.html"), None);
+        assert_eq!(parse_refresh("0; u"), None);
+        assert_eq!(parse_refresh("0; url /next.html"), None);
+    }
+}
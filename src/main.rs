@@ -1,124 +1,519 @@
-use std::collections::HashSet;
-use scraper::selector::Selector;
-use scraper::Html;
-use reqwest::{Url, StatusCode};
-use std::time::Instant;
+#[macro_use]
+mod output;
+
+mod crawler;
+mod fetch;
+mod frontier;
+mod junit;
+mod links;
+mod method;
+mod politeness;
+mod report;
+mod robots;
+mod rules;
+mod sarif;
+mod scope;
+mod status;
+
+use reqwest::Url;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant, SystemTime};
 use clap::Parser;
 
+use crate::crawler::{Config, Crawler, Failure, Referrer};
+use crate::fetch::{build_client, format_chain, ClientConfig, Fetcher, RetryPolicy};
+use crate::output::{Event, Format};
+use crate::method::{CheckMethod, CheckMethods, HostMethod};
+use crate::politeness::{parse_rate, HostLimits, Limits, Politeness};
+use crate::rules::{Pattern, Rules};
+use crate::scope::{HostPattern, Scope};
+use crate::status::{AcceptPolicy, StatusOverride, StatusSet};
+
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
 struct Arguments {
     /// The URL on which to perform the check.
-    url: String
+    url: String,
+
+    /// The maximum number of pages fetched at the same time.
+    #[clap(short, long, default_value = "8")]
+    concurrency: usize,
+
+    /// Check the links pointing to other websites, without crawling them.
+    #[clap(long)]
+    check_external: bool,
+
+    /// Only crawl the URLs located under the path of the start URL. Other
+    /// URLs of the website are considered as external.
+    #[clap(long)]
+    restrict_path: bool,
+
+    /// Consider the hosts matching this pattern as part of the website, so
+    /// that they are crawled too (e.g. `www.example.com`, `*.example.com`).
+    #[clap(long, multiple_occurrences = true)]
+    internal_host: Vec<HostPattern>,
+
+    /// Only crawl the pages matching this pattern, other links are only
+    /// checked. Patterns are regexes, or globs when prefixed with `glob:`
+    /// (e.g. `glob:**/docs/**`).
+    #[clap(long, multiple_occurrences = true)]
+    include: Vec<Pattern>,
+
+    /// Neither check nor crawl the links matching this pattern (e.g. `/admin/`).
+    #[clap(long, multiple_occurrences = true)]
+    exclude: Vec<Pattern>,
+
+    /// Check the links matching this pattern without crawling them (e.g.
+    /// `glob:**/changelog/**`).
+    #[clap(long, multiple_occurrences = true)]
+    no_crawl: Vec<Pattern>,
+
+    /// The User-Agent header sent with every request. Its product token (e.g.
+    /// `fuze`) selects the rules of the robots.txt files.
+    #[clap(long, default_value = concat!("fuze/", env!("CARGO_PKG_VERSION")))]
+    user_agent: String,
+
+    /// Ignore the robots.txt files, e.g. to crawl your own staging environment.
+    #[clap(long)]
+    ignore_robots: bool,
+
+    /// An additional header sent with every request, in the `Name: value` format.
+    #[clap(short = 'H', long = "header", multiple_occurrences = true)]
+    headers: Vec<String>,
+
+    /// The maximum number of redirects followed for a single request. Use 0
+    /// to disable redirects.
+    #[clap(long, default_value = "10")]
+    max_redirects: usize,
+
+    /// The maximum size of a downloaded page (e.g. `512KB`, `10MB`). Larger
+    /// pages are truncated. Only HTML pages are downloaded.
+    #[clap(long, default_value = "10MB", parse(try_from_str = parse_size))]
+    max_body_size: usize,
+
+    /// The URL of a proxy through which every request is sent.
+    #[clap(long)]
+    proxy: Option<String>,
+
+    /// The maximum time allowed to connect to a server, and then to receive
+    /// the whole response (e.g. `500ms`, `30s`, `2m`).
+    #[clap(long, default_value = "30s", parse(try_from_str = parse_positive_duration))]
+    timeout: Duration,
+
+    /// The maximum number of link hops from the start URL.
+    #[clap(long)]
+    max_depth: Option<usize>,
+
+    /// The maximum number of pages of the website to visit.
+    #[clap(long)]
+    max_pages: Option<usize>,
+
+    /// The time budget of the whole crawl (e.g. `30s`, `10m`, `1h`). Once
+    /// elapsed, Fuze stops and reports what has been checked so far.
+    #[clap(long, parse(try_from_str = parse_duration))]
+    max_duration: Option<Duration>,
+
+    /// The maximum number of requests per second sent to a single host.
+    #[clap(long, parse(try_from_str = parse_rate))]
+    rate_limit: Option<f64>,
+
+    /// The maximum number of requests in flight to a single host.
+    #[clap(long)]
+    max_per_host: Option<usize>,
+
+    /// The limits of the hosts matching a pattern, in the
+    /// `<host>=<rate>[,<max in flight>]` format (e.g. `*.example.com=2,4`).
+    #[clap(long, multiple_occurrences = true)]
+    host_limit: Vec<HostLimits>,
+
+    /// The number of times a request is retried after a network error or a
+    /// transient status code (408, 429, 500, 502, 503 and 504).
+    #[clap(long, default_value = "2")]
+    retries: u32,
+
+    /// The delay before the first retry, doubled after every attempt. The
+    /// `Retry-After` header of the server takes precedence.
+    #[clap(long, default_value = "500ms", parse(try_from_str = parse_duration))]
+    retry_delay: Duration,
+
+    /// The status codes denoting a working link, as a comma separated list
+    /// of codes and ranges (e.g. `200-299,301,308`).
+    #[clap(long, default_value = "200-299")]
+    accept: StatusSet,
+
+    /// The status codes accepted for the URLs matching a regex, in the
+    /// `<regex>=<codes>` format (e.g. `/api/=200-299,401`).
+    #[clap(long, multiple_occurrences = true)]
+    accept_for: Vec<StatusOverride>,
+
+    /// How external links are checked: `head` sends a HEAD request and falls
+    /// back to GET when it fails, `get` always sends a GET request.
+    #[clap(long, default_value = "head")]
+    external_method: CheckMethod,
+
+    /// The check method of the external hosts matching a pattern, in the
+    /// `<host>=<head|get>` format (e.g. `*.example.com=get`).
+    #[clap(long, multiple_occurrences = true)]
+    external_method_for: Vec<HostMethod>,
+
+    /// The format of the results written to stdout: `text`, `json` for a
+    /// single report, `ndjson` for a stream of events, `junit` for a JUnit
+    /// XML report, or `sarif` for a SARIF report. With other formats than
+    /// `text`, the progress is written to stderr.
+    #[clap(long, default_value = "text")]
+    format: Format,
 }
 
-/// Normalize an URL by rebuilding a valid addressable link.
-fn normalize_url(url: &str, path: &str) -> Option<String> {
-    // If the href attribute is an anchor, we want to ignore it
-    if path.starts_with("#") {
-        return None
-    }
+/// The longest duration accepted by the options.
+const MAX_DURATION: Duration = Duration::from_secs(100 * 365 * 24 * 3600);
 
-    let base_url = Url::parse(url).unwrap();
+/// Parse a duration made of a number and an optional unit (`ms`, `s`, `m`
+/// or `h`). A number without unit is a number of seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
 
-    return match Url::parse(path) {
-        Ok(href) => if href.has_host() && href.host() == base_url.host() { Some(href.to_string()) } else { None },
-        Err(_) => {
-            // If the path is relative, we can simply return the concatenation
-            if path.starts_with("/") {
-                return Some(format!("{}://{}{}", base_url.scheme(), base_url.domain().unwrap(), path))
-            }
-            Some(format!("{}://{}/{}", base_url.scheme(), base_url.domain().unwrap(), path))
-        }
+    let amount = amount
+        .parse::<f64>()
+        .map_err(|_| format!("'{}' is not a valid duration", value))?;
+    let seconds = match unit {
+        "ms" => amount / 1000.0,
+        "" | "s" => amount,
+        "m" => amount * 60.0,
+        "h" => amount * 3600.0,
+        _ => return Err(format!("'{}' is not a valid duration unit, expected ms, s, m or h", unit)),
     };
+
+    // Timers can't be scheduled too far in the future
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|duration| *duration <= MAX_DURATION)
+        .ok_or_else(|| format!("'{}' is not a valid duration, it can't exceed 100 years", value))
 }
 
-/// Send a request to the URL provided in params and return true if the
-/// request status code is 200
-async fn check_url(url: &str) -> Result<(StatusCode, bool, String), reqwest::Error> {
-    let response = reqwest::get(url).await?;
-    Ok((
-        response.status(),
-        response.status() == 200,
-        response.text().await?
-    ))
+/// Parse a duration which can't be zero, like a timeout which would make
+/// every request fail.
+fn parse_positive_duration(value: &str) -> Result<Duration, String> {
+    match parse_duration(value)? {
+        duration if duration.is_zero() => Err(format!("'{}' is not a valid duration, it must be greater than zero", value.trim())),
+        duration => Ok(duration),
+    }
 }
 
-/// Parse a raw HTML and returns a HashSet of links.
-fn get_links_from_raw_html(url: &str, html: &str) -> HashSet<String> {
-    let document = Html::parse_document(&html);
-    let selector = Selector::parse("a[href]").unwrap();
+/// Parse a size in bytes, with an optional unit (e.g. `512KB`, `10MB`).
+fn parse_size(value: &str) -> Result<usize, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
 
-    document
-        .select(&selector)
-        .filter_map(|el| normalize_url(url, el.value().attr("href").unwrap()))
-        .collect::<HashSet<String>>()
+    let amount = amount
+        .parse::<usize>()
+        .map_err(|_| format!("'{}' is not a valid size", value))?;
+    let multiplier = match unit.to_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        _ => return Err(format!("'{}' is not a valid size unit, expected B, KB, MB or GB", unit)),
+    };
+
+    amount
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("'{}' is not a valid size", value))
 }
 
 /// Format and ensure the URL provided by the user is valid
-fn format_url(url: &str) -> String {
-    let parsed = Url::parse(url)
-        .map_err(|_| {
-            println!("😥 Oh no ! '{}' is not a valid URL. Please check that the URL is valid an retry.", &url);
+fn format_url(url: &str) -> Url {
+    let mut parsed = match Url::parse(url) {
+        Ok(parsed) if parsed.has_host() && matches!(parsed.scheme(), "http" | "https") => parsed,
+        _ => {
+            progress!("😥 Oh no ! '{}' is not a valid URL. Please check that the URL is valid an retry.", &url);
             std::process::exit(1);
-        })
-        .unwrap();
-
-    format!(
-        "{}://{}/",
-        parsed.scheme(),
-        parsed.domain().unwrap()
-    )
+        }
+    };
+
+    parsed.set_fragment(None);
+    parsed
+}
+
+/// Print the pages referencing a link.
+fn print_referrers(link: &str, referrers: &HashMap<String, Vec<Referrer>>) {
+    referrers
+        .get(link)
+        .into_iter()
+        .flatten()
+        .for_each(|referrer| progress!("     ↳ found on {}", referrer));
+}
+
+/// Print broken links grouped by category to ease the analysis, each one
+/// followed by the pages referencing it.
+fn print_broken_links(broken: &[(&String, &Failure)], referrers: &HashMap<String, Vec<Referrer>>) {
+    let mut categories = BTreeMap::new();
+    for (link, failure) in broken {
+        categories.entry(failure.category()).or_insert_with(Vec::new).push((link, failure));
+    }
+
+    for (category, mut links) in categories {
+        links.sort_by(|a, b| a.0.cmp(b.0));
+        progress!("{} ({}):", category, links.len());
+        for (link, failure) in links {
+            progress!("  ❌ {} ({})", link, failure);
+            print_referrers(link, referrers);
+        }
+    }
 }
 
 #[tokio::main]
-async fn main() -> Result<(), reqwest::Error> {
+async fn main() {
     let args = Arguments::parse();
+    output::set_format(args.format);
     let url = format_url(&args.url);
 
-    println!("🚀 Fuze starting analysis of {}", &url);
-
-    let mut visited = HashSet::<String>::new();
-    let mut broken_link = HashSet::<String>::new();
-    let mut to_visit = HashSet::<String>::new();
+    let client = build_client(&ClientConfig {
+        user_agent: args.user_agent.clone(),
+        proxy: args.proxy,
+        headers: args.headers,
+        timeout: args.timeout,
+    })
+    .unwrap_or_else(|error| {
+        progress!("😥 Oh no ! {}. Please check the options and retry.", error);
+        std::process::exit(1);
+    });
 
-    to_visit.insert(url);
+    progress!("🚀 Fuze starting analysis of {}", &url);
 
     let start_time = Instant::now();
+    let started_at = SystemTime::now();
 
-    while !to_visit.is_empty() {
-        for url in to_visit.clone().drain() {
-            let (status, is_ok, html) = check_url(&url).await?;
-
-            visited.insert(url.to_string());
-
-            let links = get_links_from_raw_html(&url, &html)
-                .difference(&visited)
-                .map(|el| el.to_string())
-                .collect::<HashSet<String>>();
-
-            if !is_ok {
-                println!("❌ {} [{}]", &url, &status);
-                broken_link.insert(url.clone());
-            } else {
-                println!("✅ {} [{}]", &url, &status);
-                if !links.is_empty() {
-                    println!("➡️ {} link(s) reconciled.", &links.len());
-                }
-            }
+    let options = report::Options {
+        concurrency: args.concurrency,
+        check_external: args.check_external,
+        restrict_path: args.restrict_path,
+        internal_hosts: args.internal_host.iter().map(ToString::to_string).collect(),
+        include: args.include.iter().map(ToString::to_string).collect(),
+        exclude: args.exclude.iter().map(ToString::to_string).collect(),
+        no_crawl: args.no_crawl.iter().map(ToString::to_string).collect(),
+        user_agent: args.user_agent.clone(),
+        ignore_robots: args.ignore_robots,
+        max_redirects: args.max_redirects,
+        max_body_size: args.max_body_size,
+        timeout_ms: report::millis(args.timeout),
+        max_depth: args.max_depth,
+        max_pages: args.max_pages,
+        max_duration_ms: args.max_duration.map(report::millis),
+        rate_limit: args.rate_limit,
+        max_per_host: args.max_per_host,
+        host_limits: args.host_limit.iter().map(ToString::to_string).collect(),
+        retries: args.retries,
+        retry_delay_ms: report::millis(args.retry_delay),
+        accept: args.accept.to_string(),
+        accept_for: args.accept_for.iter().map(ToString::to_string).collect(),
+        external_method: args.external_method.to_string(),
+        external_method_for: args.external_method_for.iter().map(ToString::to_string).collect(),
+    };
 
-            to_visit = links;
-        }
+    let scope = Scope::new(&url, args.restrict_path, args.internal_host.clone());
+    if scope.path_prefix() == Some("/") {
+        progress!("⚠️ The start URL is at the root of the website, --restrict-path has no effect.");
     }
 
-    println!("👻 Done ! Fuze visited {} links in {:?}.", &visited.len(), &start_time.elapsed());
+    let config = Config {
+        concurrency: args.concurrency,
+        check_external: args.check_external,
+        scope,
+        rules: Rules {
+            include: args.include,
+            exclude: args.exclude,
+            no_crawl: args.no_crawl,
+        },
+        accept: AcceptPolicy {
+            default: args.accept,
+            overrides: args.accept_for,
+        },
+        check_methods: CheckMethods {
+            default: args.external_method,
+            overrides: args.external_method_for,
+        },
+        user_agent: args.user_agent,
+        ignore_robots: args.ignore_robots,
+        politeness: Politeness {
+            default: Limits {
+                rate: args.rate_limit,
+                max_in_flight: args.max_per_host,
+            },
+            overrides: args.host_limit,
+        },
+        max_depth: args.max_depth,
+        max_pages: args.max_pages,
+        max_duration: args.max_duration,
+    };
+    let retry = RetryPolicy {
+        retries: args.retries,
+        delay: args.retry_delay,
+    };
+    let fetcher = Fetcher::new(client, retry, args.max_redirects, args.max_body_size);
+    let report = Crawler::new(config, fetcher).run(url.to_string()).await;
 
-    if broken_link.len() > 0 {
-        println!("Found {} broken links !", broken_link.len());
-        broken_link.iter().for_each(|link| println!("❌ {}", link));
+    progress!("👻 Done ! Fuze visited {} links in {:?}.", &report.visited.len(), &start_time.elapsed());
+    if report.incomplete {
+        progress!("⏱️ The maximum duration of the crawl has been reached, this report is incomplete.");
+    }
+    if !report.filtered.is_empty() {
+        let mut filtered = report.filtered.iter().collect::<Vec<_>>();
+        filtered.sort();
+        progress!("🧹 Links filtered by rules:");
+        filtered.iter().for_each(|(rule, count)| progress!("  {} link(s) by {}", count, rule));
+    }
+    if !report.unvisited.is_empty() {
+        progress!("⛔ {} discovered link(s) were left unvisited because of the depth or page limits.", report.unvisited.len());
+    }
+    if args.check_external {
+        progress!("🌍 Fuze checked {} external links.", &report.external.len());
+    }
+
+    let (external, internal): (Vec<_>, Vec<_>) = report.broken
+        .iter()
+        .partition(|(link, _)| report.external.contains(*link));
+
+    if !internal.is_empty() {
+        progress!("Found {} broken links !", internal.len());
+        print_broken_links(&internal, &report.referrers);
     } else {
-        println!("No broken link detected !");
+        progress!("No broken link detected !");
+    }
+
+    if !external.is_empty() {
+        progress!("Found {} broken external links !", external.len());
+        print_broken_links(&external, &report.referrers);
+    }
+
+    if !report.missing_anchors.is_empty() {
+        let mut missing = report.missing_anchors.keys().collect::<Vec<_>>();
+        missing.sort();
+        progress!("Found {} missing anchors !", missing.len());
+        for link in missing {
+            progress!("  ⚓ {}", link);
+            print_referrers(link, &report.missing_anchors);
+        }
+    }
+
+    // Internal links should point to their final location, so that authors
+    // can update them
+    let mut redirected = report.redirects
+        .iter()
+        .filter(|(link, _)| !report.external.contains(*link))
+        .collect::<Vec<_>>();
+    if !redirected.is_empty() {
+        redirected.sort_by(|a, b| a.0.cmp(b.0));
+        progress!("↪️ {} internal link(s) are redirected:", redirected.len());
+        for (link, chain) in redirected {
+            progress!("  ⚠️ {}", format_chain(chain));
+            let final_host = chain.last().and_then(|redirect| Url::parse(&redirect.location).ok()?.host_str().map(str::to_string));
+            if final_host.as_deref() != Url::parse(link).ok().as_ref().and_then(Url::host_str) {
+                progress!("     ⚠️ the chain ends on another host");
+            }
+            print_referrers(link, &report.referrers);
+        }
     }
 
-    Ok(())
+    if !report.disallowed.is_empty() {
+        let mut disallowed = report.disallowed.iter().collect::<Vec<_>>();
+        disallowed.sort();
+        progress!("🤖 {} link(s) were not checked because of robots.txt rules:", disallowed.len());
+        disallowed.iter().for_each(|link| progress!("  ⏭️ {}", link));
+    }
+
+    if !report.retried.is_empty() {
+        let mut retried = report.retried.iter().collect::<Vec<_>>();
+        retried.sort();
+        progress!("🔁 {} link(s) only succeeded after being retried:", retried.len());
+        retried.iter().for_each(|(link, attempts)| progress!("  ⚠️ {} ({} attempts)", link, attempts));
+    }
+
+    output::emit(&Event::Finished {
+        duration_ms: report::millis(start_time.elapsed()),
+        incomplete: report.incomplete,
+        summary: report::summarize(&report),
+    });
+
+    if args.format == Format::Json {
+        let run = report::Run {
+            tool: "fuze",
+            version: env!("CARGO_PKG_VERSION"),
+            seed: url.to_string(),
+            started_at: report::timestamp(started_at),
+            finished_at: report::timestamp(SystemTime::now()),
+            duration_ms: report::millis(start_time.elapsed()),
+            incomplete: report.incomplete,
+            options,
+        };
+        println!("{}", serde_json::to_string_pretty(&report::build(&report, run)).unwrap());
+    }
+    if args.format == Format::Junit {
+        print!("{}", junit::render(&report, started_at, start_time.elapsed()));
+    }
+    if args.format == Format::Sarif {
+        println!("{}", serde_json::to_string_pretty(&sarif::render(&report, started_at)).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_units() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration(" 1h "), Ok(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid_values() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("1.2.3s").is_err());
+        assert!(parse_duration("10d").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_overflowing_values() {
+        assert!(parse_duration("99999999999999999999999").is_err());
+        assert!(parse_duration("9999999999999999h").is_err());
+        assert!(parse_duration("9999999999999h").is_err());
+        assert!(parse_duration("876000h").is_ok());
+    }
+
+    #[test]
+    fn parse_positive_duration_rejects_zero() {
+        assert!(parse_positive_duration("0").is_err());
+        assert!(parse_positive_duration("0ms").is_err());
+        assert!(parse_positive_duration("0.0001ms").is_ok());
+        assert_eq!(parse_positive_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_size_accepts_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512B"), Ok(512));
+        assert_eq!(parse_size("2kb"), Ok(2048));
+        assert_eq!(parse_size(" 10MB "), Ok(10 << 20));
+        assert_eq!(parse_size("1GB"), Ok(1 << 30));
+    }
+
+    #[test]
+    fn parse_size_rejects_invalid_values() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("-1MB").is_err());
+        assert!(parse_size("1.5MB").is_err());
+        assert!(parse_size("1TB").is_err());
+        assert!(parse_size("99999999999999999999GB").is_err());
+        assert!(parse_size(&format!("{}GB", usize::MAX)).is_err());
+    }
 }
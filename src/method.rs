@@ -0,0 +1,84 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::scope::HostPattern;
+
+/// How the status of an external link is checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckMethod {
+    /// Send a HEAD request, and fall back to a GET request if the server
+    /// doesn't handle it properly.
+    Head,
+    /// Send a GET request, without downloading the body of the response.
+    Get,
+}
+
+impl FromStr for CheckMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "head" => Ok(CheckMethod::Head),
+            "get" => Ok(CheckMethod::Get),
+            _ => Err(format!("'{}' is not a valid check method, expected head or get", value)),
+        }
+    }
+}
+
+impl fmt::Display for CheckMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckMethod::Head => "head",
+            CheckMethod::Get => "get",
+        })
+    }
+}
+
+/// The check method of the hosts matching a pattern, written as
+/// `<host pattern>=<head|get>` (e.g. `*.example.com=get`).
+#[derive(Debug, Clone)]
+pub struct HostMethod {
+    pattern: HostPattern,
+    method: CheckMethod,
+}
+
+impl FromStr for HostMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (pattern, method) = value
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' is not a valid host method, expected '<host>=<head|get>'", value))?;
+
+        Ok(HostMethod {
+            pattern: pattern.parse()?,
+            method: method.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for HostMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.pattern, self.method)
+    }
+}
+
+/// The methods used to check the external links.
+#[derive(Debug, Clone)]
+pub struct CheckMethods {
+    /// The method of the hosts without specific method.
+    pub default: CheckMethod,
+    /// The methods of specific hosts. The first matching pattern wins.
+    pub overrides: Vec<HostMethod>,
+}
+
+impl CheckMethods {
+    /// Find the method applying to a host.
+    pub fn method_for(&self, host: &str) -> CheckMethod {
+        self.overrides
+            .iter()
+            .find(|rule| rule.pattern.matches(host))
+            .map(|rule| rule.method)
+            .unwrap_or(self.default)
+    }
+}
@@ -0,0 +1,115 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+use serde::Serialize;
+
+use crate::report::{ErrorEntry, Summary};
+
+/// The format of the results written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Human-oriented progress and summary.
+    Text,
+    /// A single JSON document, described in the `report` module.
+    Json,
+    /// A stream of JSON events, one per line, written as they happen.
+    Ndjson,
+    /// A JUnit XML report, where each link is a test case.
+    Junit,
+    /// A SARIF report, where each problem is located in the page holding
+    /// the link.
+    Sarif,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "junit" => Ok(Format::Junit),
+            "sarif" => Ok(Format::Sarif),
+            _ => Err(format!("'{}' is not a valid format, expected text, json, ndjson, junit or sarif", value)),
+        }
+    }
+}
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+
+/// Select the format of the results, once and for all.
+pub fn set_format(format: Format) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> Format {
+    FORMAT.get().copied().unwrap_or(Format::Text)
+}
+
+/// Print a human-oriented line. It goes to stdout with the text format, and
+/// to stderr with the other ones, so that stdout only carries the results.
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if crate::output::format() == crate::output::Format::Text {
+            println!($($arg)*)
+        } else {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+/// An event of the crawl, written as a single line of JSON with the
+/// `ndjson` format. The type of event is given by its `event` field.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A new URL has been found on a page and queued.
+    Discovered {
+        url: &'a str,
+        page: &'a str,
+        kind: &'static str,
+        depth: usize,
+        external: bool,
+    },
+    /// An URL has been fetched.
+    Checked {
+        url: &'a str,
+        kind: &'static str,
+        depth: usize,
+        external: bool,
+        ok: bool,
+        status: Option<u16>,
+        content_type: Option<&'a str>,
+        duration_ms: u64,
+        error: Option<ErrorEntry>,
+    },
+    /// A request failed and is about to be sent again.
+    Retry {
+        url: &'a str,
+        reason: String,
+        retry: u32,
+        retries: u32,
+        delay_ms: u64,
+    },
+    /// An URL hasn't been fetched because of a rule, or of the robots.txt
+    /// rules of its host. The page is unknown for the URLs skipped once
+    /// queued.
+    Skipped {
+        url: &'a str,
+        page: Option<&'a str>,
+        rule: &'a str,
+    },
+    /// The crawl is over.
+    Finished {
+        duration_ms: u64,
+        incomplete: bool,
+        summary: Summary,
+    },
+}
+
+/// Write an event to stdout, if the format is `ndjson`.
+pub fn emit(event: &Event) {
+    if format() == Format::Ndjson {
+        println!("{}", serde_json::to_string(event).unwrap());
+    }
+}
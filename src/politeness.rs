@@ -0,0 +1,169 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::scope::HostPattern;
+
+/// The lowest rate accepted, one request per day. Lower rates would make
+/// the crawl of the host endless anyway.
+const MIN_RATE: f64 = 1.0 / 86400.0;
+
+/// Parse a number of requests per second, of at least one per day.
+pub fn parse_rate(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| rate.is_finite() && *rate >= MIN_RATE)
+        .ok_or_else(|| format!("'{}' is not a valid rate, expected a positive number of at least one request per day", value))
+}
+
+/// How hard a host can be requested.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// The maximum number of requests per second.
+    pub rate: Option<f64>,
+    /// The maximum number of requests in flight at the same time.
+    pub max_in_flight: Option<usize>,
+}
+
+impl Limits {
+    /// The minimum delay between two requests, derived from the rate.
+    pub fn interval(&self) -> Option<Duration> {
+        self.rate.and_then(|rate| Duration::try_from_secs_f64(1.0 / rate).ok())
+    }
+}
+
+/// Limits applying to the hosts matching a pattern, written as
+/// `<host pattern>=<requests per second>[,<max in flight>]` (e.g.
+/// `*.example.com=2,4`). The rate can be left empty to only limit the
+/// number of requests in flight (e.g. `cdn.example.com=,8`).
+#[derive(Debug, Clone)]
+pub struct HostLimits {
+    pattern: HostPattern,
+    limits: Limits,
+}
+
+impl FromStr for HostLimits {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not a valid host limit, expected '<host>=<rate>[,<max in flight>]'", value);
+
+        let (pattern, limits) = value.split_once('=').ok_or_else(invalid)?;
+        let (rate, max_in_flight) = match limits.split_once(',') {
+            Some((rate, max_in_flight)) => (rate.trim(), Some(max_in_flight.trim())),
+            None => (limits.trim(), None),
+        };
+
+        let rate = match rate {
+            "" => None,
+            rate => Some(parse_rate(rate).map_err(|_| invalid())?),
+        };
+        let max_in_flight = match max_in_flight {
+            Some(max) => Some(max.parse::<usize>().ok().filter(|max| *max > 0).ok_or_else(invalid)?),
+            None => None,
+        };
+
+        Ok(HostLimits {
+            pattern: pattern.parse()?,
+            limits: Limits { rate, max_in_flight },
+        })
+    }
+}
+
+impl fmt::Display for HostLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}=", self.pattern)?;
+        if let Some(rate) = self.limits.rate {
+            write!(f, "{}", rate)?;
+        }
+        if let Some(max) = self.limits.max_in_flight {
+            write!(f, ",{}", max)?;
+        }
+        Ok(())
+    }
+}
+
+/// The limits applied to every host, so that the crawl doesn't overload
+/// the servers it requests.
+#[derive(Debug, Clone)]
+pub struct Politeness {
+    /// The limits of the hosts without specific limits.
+    pub default: Limits,
+    /// The limits of specific hosts. The first matching pattern wins.
+    pub overrides: Vec<HostLimits>,
+}
+
+impl Politeness {
+    /// Find the limits applying to a host.
+    pub fn limits_for(&self, host: &str) -> Limits {
+        self.overrides
+            .iter()
+            .find(|rule| rule.pattern.matches(host))
+            .map(|rule| rule.limits)
+            .unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rate_accepts_positive_rates() {
+        assert_eq!(parse_rate("2"), Ok(2.0));
+        assert_eq!(parse_rate(" 0.5 "), Ok(0.5));
+        assert_eq!(parse_rate("0.0001"), Ok(0.0001));
+    }
+
+    #[test]
+    fn parse_rate_rejects_invalid_rates() {
+        assert!(parse_rate("").is_err());
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("-1").is_err());
+        assert!(parse_rate("1e-30").is_err());
+        assert!(parse_rate("inf").is_err());
+        assert!(parse_rate("NaN").is_err());
+    }
+
+    #[test]
+    fn host_limits_parse_rate_and_max_in_flight() {
+        let limits = "*.example.com=2,4".parse::<HostLimits>().unwrap();
+        assert_eq!(limits.limits.rate, Some(2.0));
+        assert_eq!(limits.limits.max_in_flight, Some(4));
+
+        let limits = "example.com=,8".parse::<HostLimits>().unwrap();
+        assert_eq!(limits.limits.rate, None);
+        assert_eq!(limits.limits.max_in_flight, Some(8));
+
+        let limits = "example.com=0.5".parse::<HostLimits>().unwrap();
+        assert_eq!(limits.limits.rate, Some(0.5));
+        assert_eq!(limits.limits.max_in_flight, None);
+    }
+
+    #[test]
+    fn host_limits_reject_invalid_values() {
+        assert!("example.com".parse::<HostLimits>().is_err());
+        assert!("example.com=1e-30".parse::<HostLimits>().is_err());
+        assert!("example.com=inf".parse::<HostLimits>().is_err());
+        assert!("example.com=NaN".parse::<HostLimits>().is_err());
+        assert!("example.com=1,0".parse::<HostLimits>().is_err());
+        assert!("=1".parse::<HostLimits>().is_err());
+    }
+
+    #[test]
+    fn interval_is_the_inverse_of_the_rate() {
+        let limits = Limits { rate: Some(4.0), max_in_flight: None };
+        assert_eq!(limits.interval(), Some(Duration::from_millis(250)));
+        assert_eq!(Limits::default().interval(), None);
+    }
+
+    #[test]
+    fn host_limits_display_their_limits() {
+        let display = |value: &str| value.parse::<HostLimits>().unwrap().to_string();
+        assert_eq!(display("*.example.com=2,4"), "*.example.com=2,4");
+        assert_eq!(display("example.com=0.5"), "example.com=0.5");
+        assert_eq!(display("cdn.example.com=,8"), "cdn.example.com=,8");
+    }
+}
@@ -0,0 +1,274 @@
+//! The JSON report, written to stdout with `--format json`.
+//!
+//! The report is a single object whose layout is identified by its
+//! `schema_version`. The version is bumped whenever a field is removed, or
+//! changes its type or meaning. New fields may be added without bumping it,
+//! so consumers should ignore the fields they don't know.
+//!
+//! Version 1 is made of:
+//! - `schema_version`: the version of this layout, currently `1`.
+//! - `run`: how the crawl was run (see [`Run`]).
+//! - `summary`: the counts of the report (see [`Summary`]).
+//! - `urls`: every fetched URL, sorted alphabetically (see [`UrlEntry`]).
+//! - `missing_anchors`: every link whose fragment matches no anchor of its
+//!   page (see [`AnchorEntry`]).
+//! - `disallowed`: the URLs not fetched because of robots.txt rules.
+//! - `unvisited`: the URLs left aside because of the depth or page limits.
+//! - `filtered`: the number of links filtered by each rule.
+//!
+//! Timestamps are RFC 3339 strings in UTC, durations are integers in
+//! milliseconds and missing values are `null`.
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+use serde::Serialize;
+
+use crate::crawler::{CrawlReport, Referrer};
+use crate::fetch::Redirect;
+
+/// The version of the layout of the report.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub schema_version: u32,
+    pub run: Run,
+    pub summary: Summary,
+    pub urls: Vec<UrlEntry>,
+    pub missing_anchors: Vec<AnchorEntry>,
+    pub disallowed: Vec<String>,
+    pub unvisited: Vec<String>,
+    pub filtered: BTreeMap<String, usize>,
+}
+
+/// How the crawl was run.
+#[derive(Debug, Serialize)]
+pub struct Run {
+    /// Always `fuze`.
+    pub tool: &'static str,
+    /// The version of Fuze which produced the report.
+    pub version: &'static str,
+    /// The URL the crawl started from.
+    pub seed: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub duration_ms: u64,
+    /// Whether the crawl was interrupted by `--max-duration`.
+    pub incomplete: bool,
+    pub options: Options,
+}
+
+/// The options of the crawl. Headers and proxy are left out, as they may
+/// hold credentials.
+#[derive(Debug, Serialize)]
+pub struct Options {
+    pub concurrency: usize,
+    pub check_external: bool,
+    pub restrict_path: bool,
+    pub internal_hosts: Vec<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub no_crawl: Vec<String>,
+    pub user_agent: String,
+    pub ignore_robots: bool,
+    pub max_redirects: usize,
+    pub max_body_size: usize,
+    pub timeout_ms: u64,
+    pub max_depth: Option<usize>,
+    pub max_pages: Option<usize>,
+    pub max_duration_ms: Option<u64>,
+    pub rate_limit: Option<f64>,
+    pub max_per_host: Option<usize>,
+    pub host_limits: Vec<String>,
+    pub retries: u32,
+    pub retry_delay_ms: u64,
+    pub accept: String,
+    pub accept_for: Vec<String>,
+    pub external_method: String,
+    pub external_method_for: Vec<String>,
+}
+
+/// The counts of the report.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    /// The number of fetched URLs, internal or external.
+    pub checked: usize,
+    pub internal: usize,
+    pub external: usize,
+    pub broken: usize,
+    /// The number of URLs answering with a redirect.
+    pub redirected: usize,
+    /// The number of URLs which only succeeded after being retried.
+    pub retried: usize,
+    pub missing_anchors: usize,
+    pub disallowed: usize,
+    pub unvisited: usize,
+    /// The number of distinct links filtered by rules.
+    pub filtered: usize,
+}
+
+/// A fetched URL.
+#[derive(Debug, Serialize)]
+pub struct UrlEntry {
+    pub url: String,
+    /// Whether the URL belongs to another website.
+    pub external: bool,
+    /// What the URL points to: `page`, `frame`, `image`, `icon`,
+    /// `stylesheet`, `script`, `media`, `object`, `form` or `resource`.
+    pub kind: &'static str,
+    /// The number of link hops from the seed.
+    pub depth: usize,
+    /// Whether the link works.
+    pub ok: bool,
+    /// The status of the response, `null` if the request failed.
+    pub status: Option<u16>,
+    pub content_type: Option<String>,
+    /// The time spent fetching the URL, retries and redirects included.
+    pub duration_ms: u64,
+    /// The number of attempts it took, `null` if the link is broken.
+    pub attempts: Option<u32>,
+    /// Why the link is broken, `null` if it works.
+    pub error: Option<ErrorEntry>,
+    /// The redirects followed to reach the final response.
+    pub redirects: Vec<RedirectEntry>,
+    /// The pages linking to the URL.
+    pub referrers: Vec<ReferrerEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorEntry {
+    /// One of `http`, `dns`, `connection-refused`, `connect`, `tls`,
+    /// `timeout`, `body-decode`, `too-many-redirects`, `redirect-loop` or
+    /// `other`.
+    pub category: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RedirectEntry {
+    pub url: String,
+    pub status: u16,
+    pub location: String,
+}
+
+impl From<&Redirect> for RedirectEntry {
+    fn from(redirect: &Redirect) -> Self {
+        RedirectEntry {
+            url: redirect.url.clone(),
+            status: redirect.status.as_u16(),
+            location: redirect.location.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReferrerEntry {
+    /// The URL of the page holding the link.
+    pub page: String,
+    /// The HTML element and attribute holding the link (e.g. `a` and `href`).
+    pub element: &'static str,
+    pub attribute: &'static str,
+    /// The text of the link, possibly empty.
+    pub text: String,
+}
+
+impl From<&Referrer> for ReferrerEntry {
+    fn from(referrer: &Referrer) -> Self {
+        ReferrerEntry {
+            page: referrer.page.clone(),
+            element: referrer.element,
+            attribute: referrer.attribute,
+            text: referrer.text.clone(),
+        }
+    }
+}
+
+/// A link whose fragment matches no anchor of its page.
+#[derive(Debug, Serialize)]
+pub struct AnchorEntry {
+    /// The URL of the link, fragment included.
+    pub url: String,
+    pub referrers: Vec<ReferrerEntry>,
+}
+
+/// Format a time as an RFC 3339 timestamp.
+pub fn timestamp(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+fn sorted<'a, I: IntoIterator<Item = &'a String>>(urls: I) -> Vec<String> {
+    let mut urls = urls.into_iter().cloned().collect::<Vec<_>>();
+    urls.sort();
+    urls
+}
+
+fn referrers_of(report: &CrawlReport, url: &str) -> Vec<ReferrerEntry> {
+    report.referrers.get(url).into_iter().flatten().map(ReferrerEntry::from).collect()
+}
+
+/// Count the results of a crawl.
+pub fn summarize(report: &CrawlReport) -> Summary {
+    let external = report.checks.keys().filter(|url| report.external.contains(*url)).count();
+    Summary {
+        checked: report.checks.len(),
+        internal: report.checks.len() - external,
+        external,
+        broken: report.broken.len(),
+        redirected: report.redirects.len(),
+        retried: report.retried.len(),
+        missing_anchors: report.missing_anchors.len(),
+        disallowed: report.disallowed.len(),
+        unvisited: report.unvisited.len(),
+        filtered: report.filtered.values().sum(),
+    }
+}
+
+/// Build the JSON report of a crawl.
+pub fn build(report: &CrawlReport, run: Run) -> Report {
+    let urls = sorted(report.checks.keys())
+        .into_iter()
+        .map(|url| {
+            let check = &report.checks[&url];
+            let failure = report.broken.get(&url);
+            UrlEntry {
+                external: report.external.contains(&url),
+                kind: check.kind.label(),
+                depth: check.depth,
+                ok: failure.is_none(),
+                status: check.status.map(|status| status.as_u16()),
+                content_type: check.content_type.clone(),
+                duration_ms: millis(check.duration),
+                attempts: failure.is_none().then(|| report.retried.get(&url).copied().unwrap_or(1)),
+                error: failure.map(|failure| ErrorEntry {
+                    category: failure.category().id(),
+                    message: failure.to_string(),
+                }),
+                redirects: report.redirects.get(&url).into_iter().flatten().map(RedirectEntry::from).collect(),
+                referrers: referrers_of(report, &url),
+                url,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let missing_anchors = sorted(report.missing_anchors.keys())
+        .into_iter()
+        .map(|url| AnchorEntry {
+            referrers: report.missing_anchors[&url].iter().map(ReferrerEntry::from).collect(),
+            url,
+        })
+        .collect::<Vec<_>>();
+
+    Report {
+        schema_version: SCHEMA_VERSION,
+        run,
+        summary: summarize(report),
+        urls,
+        missing_anchors,
+        disallowed: sorted(&report.disallowed),
+        unvisited: sorted(&report.unvisited),
+        filtered: report.filtered.iter().map(|(rule, count)| (rule.clone(), *count)).collect(),
+    }
+}
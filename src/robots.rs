@@ -0,0 +1,259 @@
+use std::time::Duration;
+use reqwest::Url;
+
+/// The longest crawl delay honoured. Longer delays, whether deliberate or
+/// not, would stall the crawl of the host.
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(60);
+
+/// An `Allow` or `Disallow` rule of a robots.txt file.
+#[derive(Debug, Clone)]
+struct Rule {
+    allow: bool,
+    /// The path pattern, which may contain `*` wildcards and end with `$`.
+    pattern: String,
+}
+
+impl Rule {
+    /// Check whether the rule applies to the given path.
+    fn matches(&self, path: &str) -> bool {
+        let (pattern, anchored) = match self.pattern.strip_suffix('$') {
+            Some(pattern) => (pattern, true),
+            None => (self.pattern.as_str(), false),
+        };
+        matches_pattern(pattern.as_bytes(), path.as_bytes(), anchored)
+    }
+}
+
+/// Match a path against a pattern where `*` stands for any sequence of
+/// characters. Unless anchored, the pattern only has to match a prefix.
+///
+/// The patterns come from untrusted robots.txt files, so they are matched
+/// without backtracking over every `*`: on a mismatch, only the last `*` is
+/// extended by one character, since the earlier ones can't lead to a match
+/// the last one misses. This bounds the work by the product of both lengths.
+fn matches_pattern(pattern: &[u8], path: &[u8], anchored: bool) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // The position of the last `*` in the pattern, and of the path it is at
+    let mut star = None;
+    loop {
+        if !anchored && p == pattern.len() {
+            return true
+        }
+        if s == path.len() {
+            break;
+        }
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, s));
+                p += 1;
+            }
+            Some(c) if *c == path[s] => {
+                p += 1;
+                s += 1;
+            }
+            _ => match star {
+                Some((star_p, star_s)) => {
+                    star = Some((star_p, star_s + 1));
+                    p = star_p + 1;
+                    s = star_s + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// A group of rules of a robots.txt file, applying to a set of user agents.
+#[derive(Debug, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+/// The rules of a robots.txt file applying to our user agent.
+#[derive(Debug, Clone, Default)]
+pub struct Robots {
+    rules: Vec<Rule>,
+    /// The minimum delay between two requests to the host.
+    pub crawl_delay: Option<Duration>,
+}
+
+impl Robots {
+    /// The rules of a host without robots.txt, which allow everything.
+    pub fn allow_all() -> Self {
+        Robots::default()
+    }
+
+    /// Parse a robots.txt file and keep the rules applying to the given user
+    /// agent. The groups naming the product token of the user agent (e.g.
+    /// `fuze` for `fuze/0.1.0`) take precedence over the `*` groups.
+    pub fn parse(content: &str, user_agent: &str) -> Self {
+        let token = user_agent
+            .split(|c: char| c == '/' || c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let mut groups = Vec::<Group>::new();
+        let mut in_agents = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim().to_lowercase(), value.trim()),
+                None => continue,
+            };
+
+            // Consecutive user-agent lines share the same group of rules
+            if key == "user-agent" {
+                if !in_agents {
+                    groups.push(Group::default());
+                    in_agents = true;
+                }
+                groups.last_mut().unwrap().agents.push(value.to_lowercase());
+                continue;
+            }
+            in_agents = false;
+
+            // Rules appearing before any user-agent line are ignored
+            let group = match groups.last_mut() {
+                Some(group) => group,
+                None => continue,
+            };
+
+            match key.as_str() {
+                // An empty disallow rule means that everything is allowed
+                "allow" | "disallow" if !value.is_empty() => group.rules.push(Rule {
+                    allow: key == "allow",
+                    pattern: value.to_string(),
+                }),
+                "crawl-delay" => group.crawl_delay = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|delay| delay.is_finite() && *delay >= 0.0)
+                    .map(|delay| Duration::try_from_secs_f64(delay).unwrap_or(MAX_CRAWL_DELAY).min(MAX_CRAWL_DELAY)),
+                _ => {}
+            }
+        }
+
+        let specific = groups
+            .iter()
+            .filter(|group| group.agents.contains(&token))
+            .collect::<Vec<_>>();
+        let selected = if specific.is_empty() {
+            groups.iter().filter(|group| group.agents.iter().any(|agent| agent == "*")).collect()
+        } else {
+            specific
+        };
+
+        Robots {
+            rules: selected.iter().flat_map(|group| group.rules.iter().cloned()).collect(),
+            crawl_delay: selected.iter().filter_map(|group| group.crawl_delay).max(),
+        }
+    }
+
+    /// Check whether the robots.txt rules allow to fetch the given URL. The
+    /// longest matching rule wins, and `Allow` wins over `Disallow` in case
+    /// of a tie.
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(&path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .map(|rule| rule.allow)
+            .unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(robots: &Robots, path: &str) -> bool {
+        robots.is_allowed(&Url::parse("https://example.com").unwrap().join(path).unwrap())
+    }
+
+    #[test]
+    fn parse_selects_the_groups_of_the_product_token() {
+        let content = "
+            User-agent: *
+            Disallow: /
+
+            User-agent: Googlebot
+            User-agent: FUZE
+            Disallow: /private # comment
+            Crawl-delay: 2
+        ";
+        let robots = Robots::parse(content, "fuze/0.1.0");
+        assert!(allowed(&robots, "/"));
+        assert!(!allowed(&robots, "/private/page"));
+        assert_eq!(robots.crawl_delay, Some(Duration::from_secs(2)));
+
+        let robots = Robots::parse(content, "other/1.0");
+        assert!(!allowed(&robots, "/"));
+        assert_eq!(robots.crawl_delay, None);
+    }
+
+    #[test]
+    fn parse_ignores_rules_outside_groups() {
+        let robots = Robots::parse("Disallow: /\nUser-agent: *\nDisallow:\n", "fuze");
+        assert!(allowed(&robots, "/"));
+        assert!(allowed(&Robots::allow_all(), "/anything"));
+    }
+
+    #[test]
+    fn is_allowed_supports_wildcards_and_anchors() {
+        let robots = Robots::parse("User-agent: *\nDisallow: /*.pdf$\nDisallow: /search?*q=\n", "fuze");
+        assert!(!allowed(&robots, "/docs/guide.pdf"));
+        assert!(allowed(&robots, "/docs/guide.pdf.html"));
+        assert!(!allowed(&robots, "/search?lang=en&q=fuze"));
+        assert!(allowed(&robots, "/search?lang=en"));
+    }
+
+    #[test]
+    fn is_allowed_prefers_the_longest_rule_then_allow() {
+        let robots = Robots::parse("User-agent: *\nDisallow: /docs\nAllow: /docs/public\nAllow: /page\nDisallow: /page\n", "fuze");
+        assert!(!allowed(&robots, "/docs/private"));
+        assert!(allowed(&robots, "/docs/public/index.html"));
+        assert!(allowed(&robots, "/page"));
+    }
+
+    #[test]
+    fn matches_pattern_does_not_backtrack() {
+        let path = format!("/{}", "a".repeat(80));
+        let rule = Rule { allow: false, pattern: "/*a*a*a*a*a*a*a*b".to_string() };
+        assert!(!rule.matches(&path));
+        let rule = Rule { allow: false, pattern: "/*a*a*a*a*a*a*a*a$".to_string() };
+        assert!(rule.matches(&path));
+        assert!(!rule.matches(&format!("{}b", path)));
+    }
+
+    #[test]
+    fn matches_pattern_handles_stars_and_anchors() {
+        assert!(matches_pattern(b"/a*c", b"/abbc/d", false));
+        assert!(!matches_pattern(b"/a*c", b"/abbc/d", true));
+        assert!(matches_pattern(b"/a*c", b"/abcbc", true));
+        assert!(matches_pattern(b"/**", b"/", true));
+        assert!(matches_pattern(b"", b"/anything", false));
+        assert!(!matches_pattern(b"/abc", b"/ab", false));
+        assert!(!matches_pattern(b"/a*b*c", b"/acb", false));
+    }
+
+    #[test]
+    fn parse_clamps_the_crawl_delay() {
+        let delay = |value: &str| Robots::parse(&format!("User-agent: *\nCrawl-delay: {}\n", value), "fuze").crawl_delay;
+        assert_eq!(delay("0.5"), Some(Duration::from_millis(500)));
+        assert_eq!(delay("3600"), Some(MAX_CRAWL_DELAY));
+        assert_eq!(delay("1e30"), Some(MAX_CRAWL_DELAY));
+        assert_eq!(delay("-1"), None);
+        assert_eq!(delay("inf"), None);
+        assert_eq!(delay("soon"), None);
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::str::FromStr;
+use regex::Regex;
+
+/// An URL pattern, written as a regex, or as a glob when prefixed with
+/// `glob:`. Regexes may match any part of the URL, while globs must match
+/// the whole URL: `*` matches anything but a `/`, `**` matches anything and
+/// `?` matches a single character (e.g. `glob:**/admin/**`).
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn matches(&self, url: &str) -> bool {
+        self.regex.is_match(url)
+    }
+}
+
+/// Translate a glob into an anchored regex.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let regex = match value.strip_prefix("glob:") {
+            Some(glob) => glob_to_regex(glob),
+            None => value.to_string(),
+        };
+
+        Ok(Pattern {
+            source: value.to_string(),
+            regex: Regex::new(&regex).map_err(|error| format!("'{}' is not a valid pattern: {}", value, error))?,
+        })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// What to do with a link of the website.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Check the link and crawl the page it points to.
+    Crawl,
+    /// Check the link without crawling the page it points to.
+    Check,
+    /// Neither check nor crawl the link.
+    Skip,
+}
+
+/// The rules filtering the links found during the crawl.
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    /// When not empty, only the pages matching one of these patterns are
+    /// crawled. The other links are only checked.
+    pub include: Vec<Pattern>,
+    /// The links matching one of these patterns are neither checked nor crawled.
+    pub exclude: Vec<Pattern>,
+    /// The links matching one of these patterns are checked, but not crawled.
+    pub no_crawl: Vec<Pattern>,
+}
+
+impl Rules {
+    /// Decide what to do with a link, and describe the rule which took the
+    /// decision if the link isn't crawled. External links are never crawled,
+    /// so only the exclusion rules apply to them.
+    pub fn action(&self, url: &str, external: bool) -> (Action, Option<String>) {
+        if let Some(pattern) = self.exclude.iter().find(|pattern| pattern.matches(url)) {
+            return (Action::Skip, Some(format!("--exclude {}", pattern)))
+        }
+        if external {
+            return (Action::Check, None)
+        }
+        if let Some(pattern) = self.no_crawl.iter().find(|pattern| pattern.matches(url)) {
+            return (Action::Check, Some(format!("--no-crawl {}", pattern)))
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|pattern| pattern.matches(url)) {
+            return (Action::Check, Some("--include (no match)".to_string()))
+        }
+        (Action::Crawl, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(values: &[&str]) -> Vec<Pattern> {
+        values.iter().map(|value| value.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn glob_to_regex_escapes_and_anchors() {
+        assert_eq!(glob_to_regex("a.b"), "^a\\.b$");
+        assert_eq!(glob_to_regex("*/**?"), "^[^/]*/.*[^/]$");
+    }
+
+    #[test]
+    fn glob_matches_the_whole_url() {
+        let pattern = "glob:**/admin/*".parse::<Pattern>().unwrap();
+        assert!(pattern.matches("https://example.com/admin/users"));
+        assert!(!pattern.matches("https://example.com/admin/users/1"));
+        assert!(!pattern.matches("https://example.com/admin"));
+
+        let pattern = "glob:https://example.com/?".parse::<Pattern>().unwrap();
+        assert!(pattern.matches("https://example.com/a"));
+        assert!(!pattern.matches("https://example.com/ab"));
+        assert!(!pattern.matches("https://example.com//"));
+    }
+
+    #[test]
+    fn regex_matches_any_part_of_the_url() {
+        let pattern = "/admin/".parse::<Pattern>().unwrap();
+        assert!(pattern.matches("https://example.com/admin/users"));
+        assert!("(".parse::<Pattern>().is_err());
+        assert_eq!(pattern.to_string(), "/admin/");
+    }
+
+    #[test]
+    fn action_applies_rules_in_order() {
+        let rules = Rules {
+            include: patterns(&["/docs/"]),
+            exclude: patterns(&["/docs/private/"]),
+            no_crawl: patterns(&["glob:**.pdf"]),
+        };
+        assert_eq!(rules.action("https://example.com/docs/a", false), (Action::Crawl, None));
+        assert_eq!(
+            rules.action("https://example.com/docs/private/a", false),
+            (Action::Skip, Some("--exclude /docs/private/".to_string()))
+        );
+        assert_eq!(
+            rules.action("https://example.com/docs/a.pdf", false),
+            (Action::Check, Some("--no-crawl glob:**.pdf".to_string()))
+        );
+        assert_eq!(
+            rules.action("https://example.com/blog/a", false),
+            (Action::Check, Some("--include (no match)".to_string()))
+        );
+    }
+
+    #[test]
+    fn action_only_excludes_external_links() {
+        let rules = Rules {
+            include: patterns(&["/docs/"]),
+            exclude: patterns(&["example.org/private"]),
+            no_crawl: Vec::new(),
+        };
+        assert_eq!(rules.action("https://example.org/blog", true), (Action::Check, None));
+        assert_eq!(rules.action("https://example.org/private", true).0, Action::Skip);
+        assert_eq!(Rules::default().action("https://example.com/", false), (Action::Crawl, None));
+    }
+}
@@ -0,0 +1,111 @@
+//! The SARIF 2.1.0 report, written to stdout with `--format sarif`.
+//!
+//! Each problem found on a page is a result located in the page holding the
+//! link, so that code scanning tools can map it back to its source. Its rule
+//! tells the class of the problem:
+//! - `http-error`: the link answers with an unexpected status code.
+//! - `timeout`: the link didn't answer in time.
+//! - `network-error`: the link couldn't be reached for another reason (DNS,
+//!   TLS, refused connection...).
+//! - `redirect`: the link is caught in a redirect loop or too long a chain,
+//!   or, as a warning, an internal link is redirected.
+//! - `missing-anchor`: the fragment of the link matches no anchor of its page.
+use std::time::SystemTime;
+use serde_json::{json, Value};
+
+use crate::crawler::{CrawlReport, Referrer};
+use crate::fetch::{format_chain, ErrorCategory};
+use crate::report;
+
+/// The rules of the report: identifier, description and default level.
+const RULES: [(&str, &str, &str); 5] = [
+    ("http-error", "The link answers with an unexpected status code.", "error"),
+    ("timeout", "The link didn't answer in time.", "error"),
+    ("network-error", "The link couldn't be reached.", "error"),
+    ("redirect", "The link is redirected.", "warning"),
+    ("missing-anchor", "The fragment of the link matches no anchor of the page it points to.", "warning"),
+];
+
+/// Build one result per page holding the link, or a single one located in
+/// the link itself if no page holds it (e.g. the start URL).
+fn results(rule: &str, level: &str, url: &str, problem: &str, referrers: Option<&Vec<Referrer>>, results: &mut Vec<Value>) {
+    let rule_index = RULES.iter().position(|(id, _, _)| *id == rule).unwrap_or_default();
+    let result = |page: &str, text: String| {
+        json!({
+            "ruleId": rule,
+            "ruleIndex": rule_index,
+            "level": level,
+            "message": { "text": text },
+            "locations": [{ "physicalLocation": { "artifactLocation": { "uri": page } } }],
+        })
+    };
+
+    match referrers.filter(|referrers| !referrers.is_empty()) {
+        Some(referrers) => results.extend(referrers.iter().map(|referrer| {
+            let mut text = format!("{} ({}) in {}[{}]", url, problem, referrer.element, referrer.attribute);
+            if !referrer.text.is_empty() {
+                text.push_str(&format!(" \"{}\"", referrer.text));
+            }
+            result(&referrer.page, text)
+        })),
+        None => results.push(result(url, format!("{} ({})", url, problem))),
+    }
+}
+
+/// Render the SARIF report of a crawl.
+pub fn render(report: &CrawlReport, started_at: SystemTime) -> Value {
+    let mut found = Vec::new();
+
+    let mut broken = report.broken.iter().collect::<Vec<_>>();
+    broken.sort_by(|a, b| a.0.cmp(b.0));
+    for (url, failure) in broken {
+        let rule = match failure.category() {
+            ErrorCategory::Http => "http-error",
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::TooManyRedirects | ErrorCategory::RedirectLoop => "redirect",
+            _ => "network-error",
+        };
+        results(rule, "error", url, &failure.to_string(), report.referrers.get(url), &mut found);
+    }
+
+    // Internal links should point to their final location
+    let mut redirected = report.redirects
+        .iter()
+        .filter(|(url, _)| !report.external.contains(*url) && !report.broken.contains_key(*url))
+        .collect::<Vec<_>>();
+    redirected.sort_by(|a, b| a.0.cmp(b.0));
+    for (url, chain) in redirected {
+        let problem = format!("redirected: {}", format_chain(chain));
+        results("redirect", "warning", url, &problem, report.referrers.get(url), &mut found);
+    }
+
+    let mut missing = report.missing_anchors.iter().collect::<Vec<_>>();
+    missing.sort_by(|a, b| a.0.cmp(b.0));
+    for (url, referrers) in missing {
+        results("missing-anchor", "warning", url, "missing anchor", Some(referrers), &mut found);
+    }
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "fuze",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": RULES.iter().map(|(id, description, level)| json!({
+                        "id": id,
+                        "shortDescription": { "text": description },
+                        "defaultConfiguration": { "level": level },
+                    })).collect::<Vec<_>>(),
+                },
+            },
+            "invocations": [{
+                "executionSuccessful": !report.incomplete,
+                "startTimeUtc": report::timestamp(started_at),
+                "endTimeUtc": report::timestamp(SystemTime::now()),
+            }],
+            "results": found,
+        }],
+    })
+}
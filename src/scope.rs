@@ -0,0 +1,204 @@
+use std::fmt;
+use std::str::FromStr;
+use reqwest::Url;
+
+/// Defines which URLs belong to the crawled website. URLs in the scope are
+/// crawled, the other ones are considered as external.
+#[derive(Debug, Clone)]
+pub struct Scope {
+    host: String,
+    /// The port of the start URL, unless it is a default HTTP(S) port.
+    port: Option<u16>,
+    /// When set, only the URLs of the start host whose path starts with this
+    /// prefix are in scope.
+    path_prefix: Option<String>,
+    /// Other hosts belonging to the website, whatever their port.
+    hosts: Vec<HostPattern>,
+}
+
+impl Scope {
+    /// Build the scope of a crawl starting from the given URL and spreading
+    /// over the hosts matching the given patterns. If `restrict_path` is
+    /// true, the scope of the start host is restricted to the directory of
+    /// the start URL. A last path segment without extension (e.g. `/blog`)
+    /// is considered as a directory, as it most likely is one.
+    pub fn new(start: &Url, restrict_path: bool, hosts: Vec<HostPattern>) -> Self {
+        let path_prefix = if restrict_path {
+            let path = start.path();
+            let directory = match path.rsplit('/').next() {
+                Some(segment) if !segment.is_empty() && !segment.contains('.') => format!("{}/", path),
+                _ => path[..=path.rfind('/').unwrap_or(0)].to_string(),
+            };
+            Some(directory)
+        } else {
+            None
+        };
+
+        Scope {
+            host: start.host_str().unwrap_or_default().to_string(),
+            port: custom_port(start),
+            path_prefix,
+            hosts,
+        }
+    }
+
+    /// Check whether an URL belongs to the crawled website.
+    pub fn contains(&self, url: &str) -> bool {
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+
+        // A website is usually served over both HTTP and HTTPS
+        if url.host_str() != Some(&self.host) || custom_port(&url) != self.port {
+            return url
+                .host_str()
+                .is_some_and(|host| self.hosts.iter().any(|pattern| pattern.matches(host)))
+        }
+
+        // The directory itself is reachable without its trailing slash
+        match &self.path_prefix {
+            Some(prefix) => url.path().starts_with(prefix.as_str()) || url.path() == prefix.trim_end_matches('/'),
+            None => true,
+        }
+    }
+
+    /// The path the URLs of the start host must start with, if any.
+    pub fn path_prefix(&self) -> Option<&str> {
+        self.path_prefix.as_deref()
+    }
+}
+
+/// The port of an URL, unless it is 80 or 443, which tell the scheme rather
+/// than the website.
+fn custom_port(url: &Url) -> Option<u16> {
+    url.port_or_known_default().filter(|port| *port != 80 && *port != 443)
+}
+
+/// A host name pattern, where `*` stands for any sequence of characters
+/// (e.g. `*.example.com`, which matches the subdomains of `example.com` but
+/// not `example.com` itself).
+#[derive(Debug, Clone)]
+pub struct HostPattern(String);
+
+impl HostPattern {
+    pub fn matches(&self, host: &str) -> bool {
+        fn matches(pattern: &[u8], host: &[u8]) -> bool {
+            match pattern.split_first() {
+                None => host.is_empty(),
+                Some((b'*', rest)) => (0..=host.len()).any(|skip| matches(rest, &host[skip..])),
+                Some((c, rest)) => host.first() == Some(c) && matches(rest, &host[1..]),
+            }
+        }
+        matches(self.0.as_bytes(), host.to_lowercase().as_bytes())
+    }
+}
+
+impl FromStr for HostPattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() || value.contains(|c: char| c == '/' || c == ':' || c.is_whitespace()) {
+            return Err(format!("'{}' is not a valid host pattern", value))
+        }
+        Ok(HostPattern(value.to_lowercase()))
+    }
+}
+
+impl fmt::Display for HostPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(start: &str, restrict_path: bool) -> Scope {
+        Scope::new(&Url::parse(start).unwrap(), restrict_path, Vec::new())
+    }
+
+    #[test]
+    fn scope_contains_the_start_host() {
+        let scope = scope("http://example.com/blog/post.html", false);
+        assert!(scope.contains("http://example.com/"));
+        assert!(scope.contains("http://example.com:80/about"));
+        assert!(scope.contains("https://example.com/x"));
+        assert!(!scope.contains("http://example.com:8080/"));
+        assert!(!scope.contains("http://www.example.com/"));
+        assert!(!scope.contains("not an url"));
+    }
+
+    #[test]
+    fn scope_compares_custom_ports_only() {
+        assert!(scope("http://example.com/", false).contains("https://example.com/x"));
+        assert!(scope("https://example.com/", false).contains("http://example.com:80/x"));
+        assert!(!scope("https://example.com/", false).contains("https://example.com:8443/x"));
+
+        let scope = scope("http://localhost:8080/", false);
+        assert!(scope.contains("http://localhost:8080/x"));
+        assert!(!scope.contains("http://localhost/x"));
+        assert!(!scope.contains("http://localhost:8081/x"));
+    }
+
+    #[test]
+    fn restrict_path_keeps_the_directory_of_a_file() {
+        let scope = scope("http://example.com/blog/post.html", true);
+        assert_eq!(scope.path_prefix(), Some("/blog/"));
+        assert!(scope.contains("http://example.com/blog/other.html"));
+        assert!(scope.contains("http://example.com/blog"));
+        assert!(!scope.contains("http://example.com/about"));
+    }
+
+    #[test]
+    fn restrict_path_considers_a_segment_without_extension_as_a_directory() {
+        let scope = scope("http://example.com/blog", true);
+        assert_eq!(scope.path_prefix(), Some("/blog/"));
+        assert!(scope.contains("http://example.com/blog"));
+        assert!(scope.contains("http://example.com/blog/post.html"));
+        assert!(!scope.contains("http://example.com/blogger"));
+        assert!(!scope.contains("http://example.com/"));
+
+        assert_eq!(self::scope("http://example.com/blog/", true).path_prefix(), Some("/blog/"));
+        assert_eq!(self::scope("http://example.com/", true).path_prefix(), Some("/"));
+        assert_eq!(self::scope("http://example.com/index.html", true).path_prefix(), Some("/"));
+    }
+
+    #[test]
+    fn scope_contains_the_internal_hosts_whatever_their_path() {
+        let scope = Scope::new(
+            &Url::parse("http://example.com/blog/").unwrap(),
+            true,
+            vec!["*.example.com".parse().unwrap()],
+        );
+        assert!(scope.contains("https://cdn.example.com/img/a.png"));
+        assert!(!scope.contains("https://example.org/"));
+    }
+
+    #[test]
+    fn host_pattern_matches_wildcards() {
+        let pattern = "*.Example.com".parse::<HostPattern>().unwrap();
+        assert!(pattern.matches("cdn.example.com"));
+        assert!(pattern.matches("a.b.EXAMPLE.com"));
+        assert!(!pattern.matches("example.com"));
+        assert!(!pattern.matches("cdn.example.com.evil.org"));
+        assert_eq!(pattern.to_string(), "*.example.com");
+
+        let pattern = "docs-*.example.*".parse::<HostPattern>().unwrap();
+        assert!(pattern.matches("docs-v2.example.org"));
+        assert!(!pattern.matches("blog.example.org"));
+
+        assert!("example.com".parse::<HostPattern>().unwrap().matches("example.com"));
+    }
+
+    #[test]
+    fn host_pattern_rejects_urls() {
+        assert!("".parse::<HostPattern>().is_err());
+        assert!("https://example.com".parse::<HostPattern>().is_err());
+        assert!("example.com:8080".parse::<HostPattern>().is_err());
+        assert!("example.com/blog".parse::<HostPattern>().is_err());
+        assert!("a b".parse::<HostPattern>().is_err());
+    }
+}
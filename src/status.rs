@@ -0,0 +1,170 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use regex::Regex;
+use reqwest::StatusCode;
+
+/// A set of status codes, written as a comma separated list of codes and
+/// ranges (e.g. `200-299,301,308`).
+#[derive(Debug, Clone)]
+pub struct StatusSet(Vec<RangeInclusive<u16>>);
+
+impl StatusSet {
+    pub fn contains(&self, status: StatusCode) -> bool {
+        self.0.iter().any(|range| range.contains(&status.as_u16()))
+    }
+}
+
+impl FromStr for StatusSet {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parse = |code: &str| {
+            code.trim()
+                .parse::<u16>()
+                .ok()
+                .filter(|code| (100..=999).contains(code))
+                .ok_or_else(|| format!("'{}' is not a valid status code", code.trim()))
+        };
+
+        value
+            .split(',')
+            .map(|part| match part.split_once('-') {
+                Some((start, end)) => {
+                    let range = parse(start)?..=parse(end)?;
+                    if range.is_empty() {
+                        return Err(format!("'{}' is not a valid status range, its start is after its end", part.trim()))
+                    }
+                    Ok(range)
+                }
+                None => parse(part).map(|code| code..=code),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(StatusSet)
+    }
+}
+
+impl fmt::Display for StatusSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges = self.0
+            .iter()
+            .map(|range| match range.start() == range.end() {
+                true => range.start().to_string(),
+                false => format!("{}-{}", range.start(), range.end()),
+            })
+            .collect::<Vec<_>>();
+        f.write_str(&ranges.join(","))
+    }
+}
+
+/// The status codes accepted for the URLs matching a pattern, written as
+/// `<regex>=<codes>` (e.g. `/api/=200-299,401`).
+#[derive(Debug, Clone)]
+pub struct StatusOverride {
+    pattern: Regex,
+    accept: StatusSet,
+}
+
+impl FromStr for StatusOverride {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        // The codes can't contain an equal sign, while the pattern could
+        let (pattern, codes) = value
+            .rsplit_once('=')
+            .ok_or_else(|| format!("'{}' is not a valid override, expected '<pattern>=<codes>'", value))?;
+
+        Ok(StatusOverride {
+            pattern: Regex::new(pattern).map_err(|error| format!("'{}' is not a valid pattern: {}", pattern, error))?,
+            accept: codes.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for StatusOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.pattern, self.accept)
+    }
+}
+
+/// Decides which status codes denote a working link.
+#[derive(Debug, Clone)]
+pub struct AcceptPolicy {
+    /// The status codes accepted by default.
+    pub default: StatusSet,
+    /// The status codes accepted for specific URLs. The first matching
+    /// override takes precedence over the default set.
+    pub overrides: Vec<StatusOverride>,
+}
+
+impl AcceptPolicy {
+    /// Check whether the status code received for an URL is acceptable.
+    pub fn accepts(&self, url: &str, status: StatusCode) -> bool {
+        self.overrides
+            .iter()
+            .find(|rule| rule.pattern.is_match(url))
+            .map(|rule| &rule.accept)
+            .unwrap_or(&self.default)
+            .contains(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(code: u16) -> StatusCode {
+        StatusCode::from_u16(code).unwrap()
+    }
+
+    #[test]
+    fn status_set_contains_codes_and_ranges() {
+        let set = "200-299, 301,308".parse::<StatusSet>().unwrap();
+        assert!(set.contains(status(200)));
+        assert!(set.contains(status(299)));
+        assert!(set.contains(status(301)));
+        assert!(set.contains(status(308)));
+        assert!(!set.contains(status(302)));
+        assert!(!set.contains(status(404)));
+    }
+
+    #[test]
+    fn status_set_accepts_single_code_ranges() {
+        let set = "404-404".parse::<StatusSet>().unwrap();
+        assert!(set.contains(status(404)));
+        assert!(!set.contains(status(403)));
+    }
+
+    #[test]
+    fn status_set_rejects_invalid_codes() {
+        assert!("".parse::<StatusSet>().is_err());
+        assert!("abc".parse::<StatusSet>().is_err());
+        assert!("99".parse::<StatusSet>().is_err());
+        assert!("1000".parse::<StatusSet>().is_err());
+        assert!("200-".parse::<StatusSet>().is_err());
+        assert!("200,,301".parse::<StatusSet>().is_err());
+    }
+
+    #[test]
+    fn status_set_rejects_reversed_ranges() {
+        assert!("299-200".parse::<StatusSet>().is_err());
+        assert!("200-299,404-400".parse::<StatusSet>().is_err());
+    }
+
+    #[test]
+    fn accept_policy_applies_the_first_matching_override() {
+        let policy = AcceptPolicy {
+            default: "200-299".parse().unwrap(),
+            overrides: vec!["/api/=401".parse().unwrap(), "/=403".parse().unwrap()],
+        };
+        assert!(policy.accepts("http://example.com/api/users", status(401)));
+        assert!(!policy.accepts("http://example.com/api/users", status(200)));
+        assert!(policy.accepts("http://example.com/", status(403)));
+    }
+
+    #[test]
+    fn status_sets_display_their_codes() {
+        assert_eq!(" 200-299, 301 ,308-308".parse::<StatusSet>().unwrap().to_string(), "200-299,301,308");
+        assert_eq!("/api/=200-299,401".parse::<StatusOverride>().unwrap().to_string(), "/api/=200-299,401");
+    }
+}